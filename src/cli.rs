@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: rust-trader [OPTIONS] [WORLD_FILE]
+
+Arguments:
+  [WORLD_FILE]       World file to load. Defaults to the built in world.
+
+Options:
+  --world <PATH>     Same as WORLD_FILE
+  --seed <SEED>      Seed for the random number generator. Starts a new game
+                     instead of resuming the save slot
+  --slot <NAME>      Save slot to save to and resume from [default: default]
+  --headless         Print a report of the loaded world and exit
+  --check            Validate the world file and exit
+  -h, --help         Print this help";
+
+pub const DEFAULT_SLOT: &str = "default";
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Args {
+    pub world: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub slot: String,
+    pub headless: bool,
+    pub check: bool,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            world: None,
+            seed: None,
+            slot: DEFAULT_SLOT.to_string(),
+            headless: false,
+            check: false,
+            help: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArgsError {
+    MissingValue(String),
+    InvalidValue(String, String),
+    UnknownFlag(String),
+    UnexpectedArgument(String),
+}
+
+impl std::fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgsError::MissingValue(flag) => write!(f, "{} needs a value", flag),
+            ArgsError::InvalidValue(flag, value) => {
+                write!(f, "{} is not a valid value for {}", value, flag)
+            }
+            ArgsError::UnknownFlag(flag) => write!(f, "Unknown option {}", flag),
+            ArgsError::UnexpectedArgument(arg) => write!(f, "Unexpected argument {}", arg),
+        }
+    }
+}
+
+impl Args {
+    /// Parses arguments, not including the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, ArgsError> {
+        let mut out = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |flag: &str| {
+                args.next()
+                    .ok_or_else(|| ArgsError::MissingValue(flag.to_string()))
+            };
+            match arg.as_str() {
+                "-h" | "--help" => out.help = true,
+                "--headless" => out.headless = true,
+                "--check" => out.check = true,
+                "--world" => out.world = Some(value(&arg)?.into()),
+                "--slot" => out.slot = value(&arg)?,
+                "--seed" => {
+                    let seed = value(&arg)?;
+                    out.seed = Some(
+                        seed.parse()
+                            .map_err(|_| ArgsError::InvalidValue(arg.clone(), seed))?,
+                    );
+                }
+                flag if flag.starts_with('-') => {
+                    return Err(ArgsError::UnknownFlag(flag.to_string()))
+                }
+                _ if out.world.is_none() => out.world = Some(arg.into()),
+                _ => return Err(ArgsError::UnexpectedArgument(arg)),
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+        Args::parse(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn parses_flags_and_positional_world() {
+        let args = parse(&[
+            "maps/test.toml",
+            "--seed",
+            "42",
+            "--slot",
+            "alice",
+            "--headless",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            Args {
+                world: Some("maps/test.toml".into()),
+                seed: Some(42),
+                slot: "alice".into(),
+                headless: true,
+                check: false,
+                help: false,
+            }
+        );
+        assert_eq!(parse(&[]).unwrap(), Args::default());
+        assert!(parse(&["--check"]).unwrap().check);
+    }
+
+    #[test]
+    fn reports_bad_arguments() {
+        assert_eq!(
+            parse(&["--seed"]),
+            Err(ArgsError::MissingValue("--seed".into()))
+        );
+        assert_eq!(
+            parse(&["--seed", "soon"]),
+            Err(ArgsError::InvalidValue("--seed".into(), "soon".into()))
+        );
+        assert_eq!(
+            parse(&["--fast"]),
+            Err(ArgsError::UnknownFlag("--fast".into()))
+        );
+        assert_eq!(
+            parse(&["a.toml", "b.toml"]),
+            Err(ArgsError::UnexpectedArgument("b.toml".into()))
+        );
+    }
+}
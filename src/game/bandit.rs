@@ -0,0 +1,181 @@
+use fastrand::Rng;
+
+use super::{
+    item::{ItemDatabase, ItemIndex},
+    ledger::TransactionKind,
+    player::PlayerState,
+};
+
+/// At a danger rating of 1.0 the player is ambushed about once every this many miles.
+pub const AMBUSH_MILES: f32 = 60.0;
+/// Share of each stack bandits carry off when they get their way.
+pub const STEAL_SHARE: f32 = 0.3;
+/// Extra share taken from a player who tried to run and was caught.
+pub const CAUGHT_SHARE: f32 = 0.5;
+pub const TRIBUTE_PER_BANDIT: u32 = 10;
+pub const GUARD_HIRE_COST: u32 = 25;
+/// Gold each guard is paid per day on the road.
+pub const GUARD_WAGE: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ambush {
+    pub bandits: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmbushResponse {
+    Pay,
+    Flee,
+    Fight,
+    Surrender,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AmbushOutcome {
+    pub stolen: Vec<(ItemIndex, u32)>,
+    pub gold_paid: u32,
+    pub guards_lost: u32,
+    /// The player kept everything, whether by fleeing or fighting.
+    pub escaped: bool,
+}
+
+/**
+    Rolls for an ambush on one mile of a road with the given danger rating.
+    Bandits leave alone anyone with at least twice as many guards as they have men.
+*/
+pub fn roll_ambush(rng: &mut Rng, danger: f32, guards: u32) -> Option<Ambush> {
+    if rng.f32() >= danger / AMBUSH_MILES {
+        return None;
+    }
+    let bandits = 1 + rng.u32(0..=(danger * 4.0) as u32);
+    if guards >= bandits * 2 {
+        return None;
+    }
+    Some(Ambush { bandits })
+}
+
+impl Ambush {
+    /// Gold the bandits ask for to let the player pass untouched.
+    pub fn tribute(&self) -> u32 {
+        self.bandits * TRIBUTE_PER_BANDIT
+    }
+
+    /// Chance to outrun the bandits, worse the heavier the cart.
+    pub fn flee_chance(&self, load_fraction: f32) -> f32 {
+        let base = 0.7 - 0.4 * load_fraction.clamp(0.0, 1.0);
+        base / (1.0 + 0.15 * (self.bandits - 1) as f32)
+    }
+
+    pub fn fight_chance(&self, guards: u32) -> f32 {
+        guards as f32 / (guards + self.bandits) as f32
+    }
+
+    /// Works out what happens to the player, updating their gold, goods and guards.
+    pub fn resolve(
+        &self,
+        response: AmbushResponse,
+        player: &mut PlayerState,
+        items: &ItemDatabase,
+        rng: &mut Rng,
+        day: u32,
+    ) -> AmbushOutcome {
+        let mut outcome = AmbushOutcome::default();
+        let share = match response {
+            AmbushResponse::Pay if player.gold >= self.tribute() => {
+                player.transact(
+                    day,
+                    TransactionKind::Tribute,
+                    -(self.tribute() as i64),
+                    None,
+                    items,
+                );
+                outcome.gold_paid = self.tribute();
+                outcome.escaped = true;
+                return outcome;
+            }
+            AmbushResponse::Flee => {
+                let load = match player.inventory.capacity {
+                    Some(capacity) if capacity > 0 => {
+                        player.inventory.weight(items) as f32 / capacity as f32
+                    }
+                    _ => 0.5,
+                };
+                if rng.f32() < self.flee_chance(load) {
+                    outcome.escaped = true;
+                    return outcome;
+                }
+                CAUGHT_SHARE
+            }
+            AmbushResponse::Fight if player.guards > 0 => {
+                if rng.f32() < self.fight_chance(player.guards) {
+                    outcome.escaped = true;
+                    return outcome;
+                }
+                outcome.guards_lost = 1;
+                player.guards -= 1;
+                STEAL_SHARE
+            }
+            _ => STEAL_SHARE,
+        };
+        outcome.stolen = steal(player, share, items, day);
+        outcome
+    }
+}
+
+/// Takes `share` of every stack, rounding up, and records the losses in the ledger.
+pub fn steal(
+    player: &mut PlayerState,
+    share: f32,
+    items: &ItemDatabase,
+    day: u32,
+) -> Vec<(ItemIndex, u32)> {
+    let mut stolen: Vec<(ItemIndex, u32)> = player
+        .inventory
+        .iter()
+        .map(|(item, qty)| (item, ((qty as f32 * share).ceil() as u32).min(qty)))
+        .filter(|(_, qty)| *qty > 0)
+        .collect();
+    stolen.sort();
+    for (item, qty) in &stolen {
+        let _ = player.inventory.remove_item(*item, *qty);
+        player.transact(day, TransactionKind::Theft, 0, Some((*item, *qty)), items);
+    }
+    stolen
+}
+
+/**
+    Pays guards for `days` on the road. Guards that cannot be paid leave.
+    Returns how many left.
+*/
+/// Hires `count` guards for their upfront fee. Returns `false` if the player cannot afford them.
+pub fn hire_guards(player: &mut PlayerState, count: u32, items: &ItemDatabase, day: u32) -> bool {
+    let fee = -((count * GUARD_HIRE_COST) as i64);
+    if player
+        .transact(day, TransactionKind::Hire, fee, None, items)
+        .is_none()
+    {
+        return false;
+    }
+    player.guards += count;
+    true
+}
+
+pub fn pay_guards(player: &mut PlayerState, days: u32, items: &ItemDatabase, day: u32) -> u32 {
+    let wage = GUARD_WAGE * days;
+    if player.guards == 0 || wage == 0 {
+        return 0;
+    }
+    let affordable = (player.gold / wage).min(player.guards);
+    let left = player.guards - affordable;
+    player.guards = affordable;
+    if affordable > 0 {
+        player.transact(
+            day,
+            TransactionKind::Wages,
+            -((affordable * wage) as i64),
+            None,
+            items,
+        );
+    }
+    left
+}
@@ -1,13 +1,23 @@
 use crate::graph::Edge;
 use serde::Deserialize;
 
-use super::item::Inventory;
+use std::collections::HashSet;
+
+use super::{crafting::Production, item::Inventory, market::Market, market::MarketData};
 
 #[derive(Deserialize, Debug)]
 pub struct CityData {
     pub name: String,
     pub description: String,
+    pub region: Option<String>,
     pub roads: Vec<RoadData>,
+    #[serde(default)]
+    pub market: Vec<MarketData>,
+    #[serde(default)]
+    pub workshops: Vec<String>,
+    /// Recipes the city runs on its own stock.
+    #[serde(default)]
+    pub recipes: Vec<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -15,6 +25,11 @@ pub struct RoadData {
     pub name: String,
     pub to: String,
     pub distance: u32,
+    #[serde(default)]
+    pub toll: u32,
+    /// How likely bandits are to strike, from 0.0 (safe) to 1.0.
+    #[serde(default)]
+    pub danger: f32,
 }
 
 impl Edge for RoadData {
@@ -26,7 +41,11 @@ impl Edge for RoadData {
 pub struct City {
     pub name: String,
     pub description: String,
+    pub region: Option<String>,
     pub inventory: Inventory,
+    pub market: Market,
+    pub workshops: HashSet<String>,
+    pub production: Vec<Production>,
 }
 
 impl From<&CityData> for City {
@@ -34,7 +53,11 @@ impl From<&CityData> for City {
         City {
             name: value.name.clone(),
             description: value.description.clone(),
+            region: value.region.clone(),
             inventory: Inventory::new(),
+            market: Market::new(),
+            workshops: value.workshops.iter().cloned().collect(),
+            production: Vec::new(),
         }
     }
 }
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+pub const HOURS_PER_DAY: u32 = 24;
+pub const DAYS_PER_SEASON: u32 = 30;
+pub const SEASONS: [Season; 4] = [
+    Season::Spring,
+    Season::Summer,
+    Season::Autumn,
+    Season::Winter,
+];
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Season {
+    #[default]
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl std::fmt::Display for Season {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Game time, counted in whole hours since the start of the run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Clock {
+    hours: u32,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self { hours: 0 }
+    }
+
+    pub fn at(day: u32, hour: u32) -> Self {
+        Self {
+            hours: day * HOURS_PER_DAY + hour,
+        }
+    }
+
+    pub fn day(&self) -> u32 {
+        self.hours / HOURS_PER_DAY
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.hours % HOURS_PER_DAY
+    }
+
+    pub fn total_hours(&self) -> u32 {
+        self.hours
+    }
+
+    /// Years start at 1, so the first spring is "Spring of year 1".
+    pub fn year(&self) -> u32 {
+        self.day() / (DAYS_PER_SEASON * SEASONS.len() as u32) + 1
+    }
+
+    pub fn season(&self) -> Season {
+        SEASONS[(self.day() / DAYS_PER_SEASON) as usize % SEASONS.len()]
+    }
+
+    /// Moves the clock forward, returning the elapsed time in (fractional) days.
+    pub fn advance_hours(&mut self, hours: u32) -> f32 {
+        self.hours += hours;
+        hours as f32 / HOURS_PER_DAY as f32
+    }
+
+    pub fn advance_days(&mut self, days: u32) -> f32 {
+        self.advance_hours(days * HOURS_PER_DAY)
+    }
+}
+
+impl std::fmt::Display for Clock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Day {}, {:02}:00, {} of year {}",
+            self.day(),
+            self.hour(),
+            self.season(),
+            self.year()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seasons_cycle_through_the_year() {
+        let mut clock = Clock::new();
+        assert_eq!(clock.season(), Season::Spring);
+        assert_eq!(clock.advance_hours(36), 1.5);
+        assert_eq!((clock.day(), clock.hour()), (1, 12));
+
+        clock.advance_days(DAYS_PER_SEASON * 3);
+        assert_eq!(clock.season(), Season::Winter);
+        clock.advance_days(DAYS_PER_SEASON);
+        assert_eq!(clock.season(), Season::Spring);
+        assert_eq!(clock.year(), 2);
+        assert_eq!(clock.to_string(), "Day 121, 12:00, Spring of year 2");
+    }
+}
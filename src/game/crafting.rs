@@ -0,0 +1,222 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::graph::Graph;
+
+use super::{
+    city::{City, RoadData},
+    item::{Inventory, ItemDatabase, ItemIndex},
+};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct IngredientData {
+    pub item: String,
+    pub quantity: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RecipeData {
+    pub key: String,
+    pub name: String,
+    pub inputs: Vec<IngredientData>,
+    pub outputs: Vec<IngredientData>,
+    /// Days one batch takes.
+    #[serde(default = "default_recipe_days")]
+    pub days: u32,
+    /// Only this city can run the recipe.
+    pub city: Option<String>,
+    /// Only cities with this workshop can run the recipe.
+    pub workshop: Option<String>,
+}
+
+fn default_recipe_days() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecipeIndex(usize);
+
+#[derive(Debug)]
+pub struct Recipe {
+    pub key: String,
+    pub name: String,
+    pub inputs: Vec<(ItemIndex, u32)>,
+    pub outputs: Vec<(ItemIndex, u32)>,
+    pub days: u32,
+    pub city: Option<String>,
+    pub workshop: Option<String>,
+}
+
+impl Recipe {
+    /// Whether `city` has what the recipe needs, ignoring ingredients.
+    pub fn can_run_in(&self, city: &City) -> Result<(), CraftError> {
+        if let Some(required) = &self.city {
+            if *required != city.name {
+                return Err(CraftError::WrongCity(required.clone()));
+            }
+        }
+        if let Some(workshop) = &self.workshop {
+            if !city.workshops.contains(workshop) {
+                return Err(CraftError::MissingWorkshop(workshop.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn has_inputs(&self, inventory: &Inventory) -> Result<(), CraftError> {
+        for (item, quantity) in &self.inputs {
+            if inventory.quantity(*item) < *quantity {
+                return Err(CraftError::MissingInput(*item));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the outputs fit within the inventory's capacity once the inputs are used up.
+    pub fn fits(&self, inventory: &Inventory, items: &ItemDatabase) -> Result<(), CraftError> {
+        let Some(capacity) = inventory.capacity else {
+            return Ok(());
+        };
+        let weigh = |ingredients: &[(ItemIndex, u32)]| {
+            ingredients
+                .iter()
+                .map(|(item, qty)| items.weight_of(*item, *qty))
+                .fold(0, u32::saturating_add)
+        };
+        let weight = inventory
+            .weight(items)
+            .saturating_sub(weigh(&self.inputs))
+            .saturating_add(weigh(&self.outputs));
+        if weight > capacity {
+            Err(CraftError::TooHeavy)
+        } else {
+            Ok(())
+        }
+    }
+
+    /**
+        Consumes one batch of inputs from `inventory` and adds the outputs to it.
+        Nothing changes unless the inputs are there and the outputs fit.
+    */
+    pub fn run(&self, inventory: &mut Inventory, items: &ItemDatabase) -> Result<(), CraftError> {
+        self.has_inputs(inventory)?;
+        self.fits(inventory, items)?;
+        for (item, quantity) in &self.inputs {
+            inventory
+                .remove_item(*item, *quantity)
+                .map_err(|_| CraftError::MissingInput(*item))?;
+        }
+        for (item, quantity) in &self.outputs {
+            inventory.add_item(*item, *quantity);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CraftError {
+    WrongCity(String),
+    MissingWorkshop(String),
+    MissingInput(ItemIndex),
+    TooHeavy,
+}
+
+impl std::fmt::Display for CraftError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CraftError::WrongCity(city) => write!(f, "This can only be made in {}", city),
+            CraftError::MissingWorkshop(workshop) => write!(f, "This needs a {}", workshop),
+            CraftError::MissingInput(_) => write!(f, "You do not have the ingredients"),
+            CraftError::TooHeavy => write!(f, "You cannot carry what this makes"),
+        }
+    }
+}
+
+pub struct RecipeBook {
+    recipes: Vec<Recipe>,
+    recipe_map: HashMap<String, RecipeIndex>,
+}
+
+impl RecipeBook {
+    pub fn new() -> Self {
+        Self {
+            recipes: Vec::new(),
+            recipe_map: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, recipe: Recipe) -> RecipeIndex {
+        let idx = RecipeIndex(self.recipes.len());
+        self.recipe_map.insert(recipe.key.clone(), idx);
+        self.recipes.push(recipe);
+        idx
+    }
+
+    pub fn get_index(&self, key: &str) -> Option<&RecipeIndex> {
+        self.recipe_map.get(key)
+    }
+
+    pub fn get_recipe(&self, idx: RecipeIndex) -> Option<&Recipe> {
+        self.recipes.get(idx.0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (RecipeIndex, &Recipe)> {
+        self.recipes
+            .iter()
+            .enumerate()
+            .map(|(index, recipe)| (RecipeIndex(index), recipe))
+    }
+}
+
+impl Default for RecipeBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A recipe a city runs on its own stock, and how far into the current batch it is.
+#[derive(Debug, Clone)]
+pub struct Production {
+    pub recipe: RecipeIndex,
+    /// Days spent on the current batch.
+    pub elapsed: f32,
+}
+
+impl Production {
+    pub fn new(recipe: RecipeIndex) -> Self {
+        Self {
+            recipe,
+            elapsed: 0.0,
+        }
+    }
+}
+
+/**
+    Runs a city's own recipes for `days`, converting its stock batch by batch.
+    A recipe that runs out of inputs sits idle until they come back.
+*/
+pub fn run_production(city: &mut City, recipes: &RecipeBook, items: &ItemDatabase, days: f32) {
+    for production in city.production.iter_mut() {
+        let recipe = recipes.get_recipe(production.recipe).unwrap();
+        production.elapsed += days;
+        while production.elapsed >= recipe.days as f32 {
+            if recipe.run(&mut city.inventory, items).is_err() {
+                production.elapsed = 0.0;
+                break;
+            }
+            production.elapsed -= recipe.days as f32;
+        }
+    }
+}
+
+/// Advances every city's production on the map by `days`.
+pub fn advance_production(
+    map: &mut Graph<City, RoadData>,
+    recipes: &RecipeBook,
+    items: &ItemDatabase,
+    days: f32,
+) {
+    for city in map.nodes_mut() {
+        run_production(city, recipes, items, days);
+    }
+}
@@ -0,0 +1,358 @@
+use fastrand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::{
+    city::{City, RoadData},
+    clock::Season,
+    item::{ItemDatabase, ItemIndex},
+    ledger::TransactionKind,
+    player::PlayerState,
+};
+
+/// One in this many miles rolls for a travel event.
+pub const EVENT_ODDS: u32 = 200;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ConditionData {
+    /// Road names the event can happen on. Any road when empty.
+    #[serde(default)]
+    pub roads: Vec<String>,
+    /// Regions of either end of the road. Any region when empty.
+    #[serde(default)]
+    pub regions: Vec<String>,
+    /// Seasons the event can happen in. Any season when empty.
+    #[serde(default)]
+    pub seasons: Vec<Season>,
+    pub min_gold: Option<u32>,
+    pub max_gold: Option<u32>,
+    /// Goods the player must be carrying.
+    #[serde(default)]
+    pub items: Vec<ItemRequirementData>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemRequirementData {
+    pub item: String,
+    #[serde(default = "default_quantity")]
+    pub quantity: u32,
+}
+
+fn default_quantity() -> u32 {
+    1
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutcomeData {
+    GainItem { item: String, quantity: u32 },
+    LoseItem { item: String, quantity: u32 },
+    Gold { amount: i64 },
+    Delay { days: u32 },
+    Detour { miles: u32 },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChoiceData {
+    pub label: String,
+    /// Shown after the choice is made.
+    pub message: Option<String>,
+    /// The choice is only offered when these hold.
+    #[serde(default)]
+    pub conditions: ConditionData,
+    #[serde(default)]
+    pub outcomes: Vec<OutcomeData>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EventData {
+    pub key: String,
+    pub prompt: String,
+    /// Relative chance against the other events that can happen on a road.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub conditions: ConditionData,
+    pub choices: Vec<ChoiceData>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl EventData {
+    /// Every item key the event refers to, for validation.
+    pub fn item_keys(&self) -> impl Iterator<Item = &str> {
+        let conditions = std::iter::once(&self.conditions)
+            .chain(self.choices.iter().map(|c| &c.conditions))
+            .flat_map(|c| c.items.iter().map(|i| i.item.as_str()));
+        let outcomes = self
+            .choices
+            .iter()
+            .flat_map(|c| c.outcomes.iter())
+            .filter_map(|o| match o {
+                OutcomeData::GainItem { item, .. } | OutcomeData::LoseItem { item, .. } => {
+                    Some(item.as_str())
+                }
+                _ => None,
+            });
+        conditions.chain(outcomes)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Condition {
+    pub roads: Vec<String>,
+    pub regions: Vec<String>,
+    pub seasons: Vec<Season>,
+    pub min_gold: Option<u32>,
+    pub max_gold: Option<u32>,
+    pub items: Vec<(ItemIndex, u32)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    GainItem(ItemIndex, u32),
+    LoseItem(ItemIndex, u32),
+    Gold(i64),
+    Delay(u32),
+    Detour(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Choice {
+    pub label: String,
+    pub message: Option<String>,
+    pub conditions: Condition,
+    pub outcomes: Vec<Outcome>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub key: String,
+    pub prompt: String,
+    pub weight: u32,
+    pub conditions: Condition,
+    pub choices: Vec<Choice>,
+}
+
+/// Where the player is when an event is rolled.
+pub struct EventContext<'a> {
+    pub road: &'a RoadData,
+    pub from: &'a City,
+    pub to: &'a City,
+    pub player: &'a PlayerState,
+    pub season: Season,
+}
+
+impl Condition {
+    fn resolve(data: &ConditionData, items: &ItemDatabase) -> Option<Condition> {
+        Some(Condition {
+            roads: data.roads.clone(),
+            regions: data.regions.clone(),
+            seasons: data.seasons.clone(),
+            min_gold: data.min_gold,
+            max_gold: data.max_gold,
+            items: data
+                .items
+                .iter()
+                .map(|i| Some((*items.get_index(&i.item)?, i.quantity)))
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    pub fn holds(&self, ctx: &EventContext) -> bool {
+        let in_region = |city: &City| {
+            city.region
+                .as_ref()
+                .is_some_and(|region| self.regions.contains(region))
+        };
+        (self.roads.is_empty() || self.roads.contains(&ctx.road.name))
+            && (self.regions.is_empty() || in_region(ctx.from) || in_region(ctx.to))
+            && (self.seasons.is_empty() || self.seasons.contains(&ctx.season))
+            && self.min_gold.is_none_or(|min| ctx.player.gold >= min)
+            && self.max_gold.is_none_or(|max| ctx.player.gold <= max)
+            && self
+                .items
+                .iter()
+                .all(|(item, qty)| ctx.player.inventory.quantity(*item) >= *qty)
+    }
+}
+
+impl Outcome {
+    fn resolve(data: &OutcomeData, items: &ItemDatabase) -> Option<Outcome> {
+        Some(match data {
+            OutcomeData::GainItem { item, quantity } => {
+                Outcome::GainItem(*items.get_index(item)?, *quantity)
+            }
+            OutcomeData::LoseItem { item, quantity } => {
+                Outcome::LoseItem(*items.get_index(item)?, *quantity)
+            }
+            OutcomeData::Gold { amount } => Outcome::Gold(*amount),
+            OutcomeData::Delay { days } => Outcome::Delay(*days),
+            OutcomeData::Detour { miles } => Outcome::Detour(*miles),
+        })
+    }
+}
+
+impl Event {
+    /// Resolves item keys, returning `None` if any are unknown.
+    pub fn resolve(data: &EventData, items: &ItemDatabase) -> Option<Event> {
+        let choices = data
+            .choices
+            .iter()
+            .map(|choice| {
+                Some(Choice {
+                    label: choice.label.clone(),
+                    message: choice.message.clone(),
+                    conditions: Condition::resolve(&choice.conditions, items)?,
+                    outcomes: choice
+                        .outcomes
+                        .iter()
+                        .map(|o| Outcome::resolve(o, items))
+                        .collect::<Option<_>>()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Event {
+            key: data.key.clone(),
+            prompt: data.prompt.clone(),
+            weight: data.weight,
+            conditions: Condition::resolve(&data.conditions, items)?,
+            choices,
+        })
+    }
+
+    /// Choices the player is currently able to make.
+    pub fn available_choices(&self, ctx: &EventContext) -> Vec<&Choice> {
+        self.choices
+            .iter()
+            .filter(|c| c.conditions.holds(ctx))
+            .collect()
+    }
+}
+
+/// What a choice did to the journey, on top of any changes to the player.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Consequences {
+    pub delay_days: u32,
+    pub detour_miles: u32,
+}
+
+impl Choice {
+    /**
+        Applies the outcomes to the player and records them in the ledger. Losses
+        take whatever is there when the player has less than the full amount, and
+        gains that do not fit are left behind.
+
+        Gold paid for goods is recorded as a purchase, and gold received for goods
+        as a sale. Anything else is a gift, or a loss for goods that are given up.
+    */
+    pub fn apply(&self, player: &mut PlayerState, items: &ItemDatabase, day: u32) -> Consequences {
+        let mut consequences = Consequences::default();
+        let mut gold = 0;
+        let mut gained = Vec::new();
+        let mut lost = Vec::new();
+        for outcome in &self.outcomes {
+            match *outcome {
+                Outcome::GainItem(item, qty) => {
+                    if player.inventory.try_add_item(item, qty, items).is_ok() {
+                        gained.push((item, qty));
+                    }
+                }
+                Outcome::LoseItem(item, qty) => {
+                    let qty = qty.min(player.inventory.quantity(item));
+                    if qty > 0 && player.inventory.remove_item(item, qty).is_ok() {
+                        lost.push((item, qty));
+                    }
+                }
+                Outcome::Gold(amount) => gold += amount,
+                Outcome::Delay(days) => consequences.delay_days += days,
+                Outcome::Detour(miles) => consequences.detour_miles += miles,
+            }
+        }
+
+        let gold = gold.max(-(player.gold as i64));
+        let trade = if gold < 0 && !gained.is_empty() {
+            Some((TransactionKind::Buy, gained.remove(0)))
+        } else if gold > 0 && !lost.is_empty() {
+            Some((TransactionKind::Sell, lost.remove(0)))
+        } else {
+            None
+        };
+        match trade {
+            Some((kind, goods)) => {
+                player.transact(day, kind, gold, Some(goods), items);
+            }
+            None if gold != 0 => {
+                player.transact(day, TransactionKind::Gift, gold, None, items);
+            }
+            None => {}
+        }
+        for goods in gained {
+            player.transact(day, TransactionKind::Gift, 0, Some(goods), items);
+        }
+        for goods in lost {
+            player.transact(day, TransactionKind::Loss, 0, Some(goods), items);
+        }
+        consequences
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EventBook {
+    events: Vec<Event>,
+    event_map: HashMap<String, usize>,
+}
+
+impl EventBook {
+    pub fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            event_map: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, event: Event) {
+        self.event_map.insert(event.key.clone(), self.events.len());
+        self.events.push(event);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Event> {
+        self.events.get(*self.event_map.get(key)?)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        self.events.iter()
+    }
+
+    /// Picks one of the events that can happen here, weighted by `Event::weight`.
+    pub fn pick(&self, rng: &mut Rng, ctx: &EventContext) -> Option<&Event> {
+        let eligible: Vec<&Event> = self
+            .events
+            .iter()
+            .filter(|e| e.weight > 0 && e.conditions.holds(ctx))
+            .filter(|e| !e.available_choices(ctx).is_empty())
+            .collect();
+        let total: u32 = eligible.iter().map(|e| e.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.u32(0..total);
+        for event in eligible {
+            if roll < event.weight {
+                return Some(event);
+            }
+            roll -= event.weight;
+        }
+        None
+    }
+
+    /// Rolls for an event on one mile of road.
+    pub fn roll(&self, rng: &mut Rng, ctx: &EventContext) -> Option<&Event> {
+        if rng.u32(0..EVENT_ODDS) != 0 {
+            return None;
+        }
+        self.pick(rng, ctx)
+    }
+}
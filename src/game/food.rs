@@ -0,0 +1,124 @@
+use super::{
+    item::{ItemDatabase, ItemIndex, ItemTag},
+    player::PlayerState,
+};
+
+/// Nutrition each member of the caravan needs per day on the road.
+pub const NUTRITION_PER_DAY: u32 = 4;
+/// Days without a full meal before the caravan starves.
+pub const STARVATION_DAYS: u32 = 5;
+/// Fraction of the usual pace a hungry caravan manages.
+pub const HUNGRY_SPEED: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hunger {
+    Fed,
+    /// Missed `days` meals in a row.
+    Hungry {
+        days: u32,
+    },
+    Starved,
+}
+
+impl Hunger {
+    pub fn from_days(days: u32) -> Self {
+        match days {
+            0 => Hunger::Fed,
+            days if days >= STARVATION_DAYS => Hunger::Starved,
+            days => Hunger::Hungry { days },
+        }
+    }
+
+    /// Multiplier on the miles covered per day.
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            Hunger::Fed => 1.0,
+            _ => HUNGRY_SPEED,
+        }
+    }
+}
+
+impl std::fmt::Display for Hunger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Hunger::Fed => write!(f, "fed"),
+            Hunger::Hungry { days } => write!(f, "hungry for {} days", days),
+            Hunger::Starved => write!(f, "starved"),
+        }
+    }
+}
+
+/// Nutrition the caravan needs each day.
+pub fn daily_need(player: &PlayerState) -> u32 {
+    NUTRITION_PER_DAY * player.caravan_size()
+}
+
+/// Total nutrition in the player's edible goods.
+pub fn food_supply(player: &PlayerState, items: &ItemDatabase) -> u32 {
+    player
+        .inventory
+        .iter()
+        .filter_map(|(item, qty)| {
+            let item = items.get_item(item)?;
+            item.has_tag(ItemTag::Edible)
+                .then_some(item.nutrition * qty)
+        })
+        .sum()
+}
+
+/// Whole days the caravan can eat from what it carries.
+pub fn days_of_food(player: &PlayerState, items: &ItemDatabase) -> u32 {
+    food_supply(player, items) / daily_need(player)
+}
+
+/**
+    Feeds the caravan for one day. Goods closest to spoiling are eaten first,
+    then those that are cheapest for their nutrition. Returns what was eaten.
+    A day without a full meal counts towards starvation, even if something was eaten.
+*/
+pub fn eat(player: &mut PlayerState, items: &ItemDatabase) -> Vec<(ItemIndex, u32)> {
+    let inventory = &player.inventory;
+    let mut food: Vec<(ItemIndex, u32, u32, u32, Option<u32>)> = inventory
+        .iter()
+        .filter_map(|(index, qty)| {
+            let item = items.get_item(index)?;
+            let expires = item
+                .shelf_life
+                .and_then(|days| Some(inventory.batches(index).first()?.acquired + days));
+            (item.has_tag(ItemTag::Edible) && item.nutrition > 0).then_some((
+                index,
+                qty,
+                item.nutrition,
+                item.value,
+                expires,
+            ))
+        })
+        .collect();
+    // Cost per point of nutrition is compared without dividing.
+    food.sort_by(|a, b| {
+        let soonest = |expires: Option<u32>| expires.unwrap_or(u32::MAX);
+        soonest(a.4)
+            .cmp(&soonest(b.4))
+            .then_with(|| (a.3 * b.2).cmp(&(b.3 * a.2)))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let mut need = daily_need(player);
+    let mut eaten = Vec::new();
+    for (item, qty, nutrition, _, _) in food {
+        if need == 0 {
+            break;
+        }
+        let amount = need.div_ceil(nutrition).min(qty);
+        let _ = player.inventory.remove_item(item, amount);
+        need = need.saturating_sub(amount * nutrition);
+        eaten.push((item, amount));
+    }
+
+    if need == 0 {
+        player.hunger_days = 0;
+    } else {
+        player.hunger_days += 1;
+    }
+    eaten
+}
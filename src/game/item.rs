@@ -1,11 +1,58 @@
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::clock::Season;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+pub enum ItemTag {
+    /// Can be eaten to stave off hunger.
+    Edible,
+    /// Produced from other items by a recipe.
+    Craftible,
+}
+
+impl std::fmt::Display for ItemTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct ItemType {
     pub key: String,
     pub name: String,
     pub value: u32,
+    #[serde(default)]
+    pub tags: BTreeSet<ItemTag>,
+    /// Weight of a single unit, counted against inventory capacity.
+    #[serde(default = "default_item_weight")]
+    pub weight: u32,
+    /// How far one unit goes towards a day's meals. Required for Edible items.
+    #[serde(default)]
+    pub nutrition: u32,
+    /// Days the item keeps before spoiling, or `None` if it never does.
+    #[serde(default)]
+    pub shelf_life: Option<u32>,
+    /// Key of the item it turns into once spoiled. Spoiled goods are lost otherwise.
+    #[serde(default)]
+    pub spoils_into: Option<String>,
+    /// Scales how much markets want the item in each season. Unlisted seasons are 1.0.
+    #[serde(default)]
+    pub seasonal_demand: BTreeMap<Season, f32>,
+}
+
+fn default_item_weight() -> u32 {
+    1
+}
+
+impl ItemType {
+    pub fn has_tag(&self, tag: ItemTag) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    pub fn demand_factor(&self, season: Season) -> f32 {
+        self.seasonal_demand.get(&season).copied().unwrap_or(1.0)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -24,16 +71,43 @@ impl ItemDatabase {
         idx
     }
 
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
     pub fn get_index(&self, key: &str) -> Option<&ItemIndex> {
         self.item_map.get(key)
     }
 
+    pub fn get_item(&self, idx: ItemIndex) -> Option<&ItemType> {
+        self.items.get(idx.0)
+    }
+
+    /// Weight of `amount` of an item, saturating at `u32::MAX`.
+    pub fn weight_of(&self, idx: ItemIndex, amount: u32) -> u32 {
+        self.get_item(idx)
+            .map_or(0, |i| i.weight)
+            .saturating_mul(amount)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ItemIndex, &ItemType)> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (ItemIndex(index), item))
+    }
+
+    pub fn items_with_tag(&self, tag: ItemTag) -> impl Iterator<Item = (ItemIndex, &ItemType)> {
+        self.iter().filter(move |(_, item)| item.has_tag(tag))
+    }
+
     pub fn get_item_by_key(&self, key: &str) -> Option<&ItemType> {
-        if let Some(idx) = self.item_map.get(key) {
-            self.items.get(idx.0)
-        } else {
-            return None;
-        }
+        let idx = self.item_map.get(key)?;
+        self.items.get(idx.0)
     }
 
     pub fn new() -> Self {
@@ -44,20 +118,304 @@ impl ItemDatabase {
     }
 }
 
+impl Default for ItemDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryError {
+    /// Fewer than `wanted` of `item` are held.
+    Insufficient {
+        item: ItemIndex,
+        have: u32,
+        wanted: u32,
+    },
+    /// Adding the goods would take the inventory past its weight capacity.
+    OverCapacity { capacity: u32, weight: u32 },
+}
+
+impl std::fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InventoryError::Insufficient { have, wanted, .. } => {
+                write!(f, "Only {} of the {} needed are available", have, wanted)
+            }
+            InventoryError::OverCapacity { capacity, weight } => write!(
+                f,
+                "That would weigh {} but only {} can be carried",
+                weight, capacity
+            ),
+        }
+    }
+}
+
+/// Units of one item that were acquired on the same day and so spoil together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Batch {
+    pub quantity: u32,
+    /// Game day the goods were made or bought.
+    pub acquired: u32,
+}
+
 #[derive(Debug)]
 pub struct Inventory {
-    pub items: HashMap<ItemIndex, u32>,
+    /// Batches of each item, oldest first.
+    items: HashMap<ItemIndex, Vec<Batch>>,
+    /// Maximum total weight, or `None` for unlimited storage.
+    pub capacity: Option<u32>,
+    /// Day new goods are stamped with. Moved forward by `advance`.
+    pub day: u32,
 }
 
 impl Inventory {
     pub fn new() -> Self {
         Self {
             items: HashMap::new(),
+            capacity: None,
+            day: 0,
         }
     }
+
+    pub fn with_capacity(capacity: u32) -> Self {
+        Self {
+            items: HashMap::new(),
+            capacity: Some(capacity),
+            day: 0,
+        }
+    }
+
+    /// Adds goods acquired today without checking capacity. Returns the new quantity.
     pub fn add_item(&mut self, item: ItemIndex, amount: u32) -> u32 {
-        let qty = self.items.entry(item).or_insert(0);
-        *qty += amount;
-        *qty
+        let day = self.day;
+        self.add_batch(
+            item,
+            Batch {
+                quantity: amount,
+                acquired: day,
+            },
+        )
+    }
+
+    /// Adds goods with their own acquisition day without checking capacity.
+    pub fn add_batch(&mut self, item: ItemIndex, batch: Batch) -> u32 {
+        let batches = self.items.entry(item).or_default();
+        if batch.quantity > 0 {
+            match batches.iter_mut().find(|b| b.acquired == batch.acquired) {
+                Some(existing) => existing.quantity += batch.quantity,
+                None => {
+                    batches.push(batch);
+                    batches.sort_by_key(|b| b.acquired);
+                }
+            }
+        }
+        let qty = batches.iter().map(|b| b.quantity).sum();
+        if qty == 0 {
+            self.items.remove(&item);
+        }
+        qty
+    }
+
+    /// Adds goods if they fit within the capacity. Returns the new quantity.
+    pub fn try_add_item(
+        &mut self,
+        item: ItemIndex,
+        amount: u32,
+        items: &ItemDatabase,
+    ) -> Result<u32, InventoryError> {
+        self.check_capacity(item, amount, items)?;
+        Ok(self.add_item(item, amount))
+    }
+
+    pub fn quantity(&self, item: ItemIndex) -> u32 {
+        self.batches(item).iter().map(|b| b.quantity).sum()
+    }
+
+    pub fn batches(&self, item: ItemIndex) -> &[Batch] {
+        self.items.get(&item).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every item held with its total quantity.
+    pub fn iter(&self) -> impl Iterator<Item = (ItemIndex, u32)> + '_ {
+        self.items
+            .iter()
+            .map(|(item, batches)| (*item, batches.iter().map(|b| b.quantity).sum()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Removes exactly `amount` of an item, returning the quantity left.
+    pub fn remove_item(&mut self, item: ItemIndex, amount: u32) -> Result<u32, InventoryError> {
+        self.take(item, amount)?;
+        Ok(self.quantity(item))
+    }
+
+    /// Removes exactly `amount` of an item, oldest first, and returns the batches taken.
+    fn take(&mut self, item: ItemIndex, amount: u32) -> Result<Vec<Batch>, InventoryError> {
+        let have = self.quantity(item);
+        if have < amount {
+            return Err(InventoryError::Insufficient {
+                item,
+                have,
+                wanted: amount,
+            });
+        }
+        let mut taken = Vec::new();
+        let mut left = amount;
+        let batches = self.items.entry(item).or_default();
+        while left > 0 {
+            let batch = &mut batches[0];
+            let qty = batch.quantity.min(left);
+            taken.push(Batch {
+                quantity: qty,
+                acquired: batch.acquired,
+            });
+            batch.quantity -= qty;
+            left -= qty;
+            if batch.quantity == 0 {
+                batches.remove(0);
+            }
+        }
+        if batches.is_empty() {
+            self.items.remove(&item);
+        }
+        Ok(taken)
+    }
+
+    /// Moves `amount` of an item into `other`, respecting its capacity. The oldest
+    /// goods go first and keep their acquisition day.
+    pub fn transfer_to(
+        &mut self,
+        other: &mut Inventory,
+        item: ItemIndex,
+        amount: u32,
+        items: &ItemDatabase,
+    ) -> Result<(), InventoryError> {
+        let have = self.quantity(item);
+        if have < amount {
+            return Err(InventoryError::Insufficient {
+                item,
+                have,
+                wanted: amount,
+            });
+        }
+        other.check_capacity(item, amount, items)?;
+        for batch in self.take(item, amount)? {
+            other.add_batch(item, batch);
+        }
+        Ok(())
+    }
+
+    /// Like `transfer_to`, but the goods arrive stamped with `other`'s current day.
+    pub fn transfer_fresh_to(
+        &mut self,
+        other: &mut Inventory,
+        item: ItemIndex,
+        amount: u32,
+        items: &ItemDatabase,
+    ) -> Result<(), InventoryError> {
+        let have = self.quantity(item);
+        if have < amount {
+            return Err(InventoryError::Insufficient {
+                item,
+                have,
+                wanted: amount,
+            });
+        }
+        other.check_capacity(item, amount, items)?;
+        self.take(item, amount)?;
+        other.add_item(item, amount);
+        Ok(())
+    }
+
+    /**
+        Moves the inventory forward to `day`, throwing out goods past their shelf
+        life or turning them into whatever they spoil into. Spoiled goods are
+        replaced regardless of capacity. Returns what spoiled.
+    */
+    pub fn advance(&mut self, day: u32, items: &ItemDatabase) -> Vec<(ItemIndex, u32)> {
+        self.day = self.day.max(day);
+        let mut spoiled = Vec::new();
+        let mut replacements = Vec::new();
+        for (item, batches) in self.items.iter_mut() {
+            let Some(item_type) = items.get_item(*item) else {
+                continue;
+            };
+            let Some(shelf_life) = item_type.shelf_life else {
+                continue;
+            };
+            let mut lost = 0;
+            batches.retain(|batch| {
+                let expires = batch.acquired + shelf_life;
+                if expires > day {
+                    return true;
+                }
+                lost += batch.quantity;
+                if let Some(into) = item_type
+                    .spoils_into
+                    .as_ref()
+                    .and_then(|k| items.get_index(k))
+                {
+                    replacements.push((
+                        *into,
+                        Batch {
+                            quantity: batch.quantity,
+                            acquired: expires,
+                        },
+                    ));
+                }
+                false
+            });
+            if lost > 0 {
+                spoiled.push((*item, lost));
+            }
+        }
+        self.items.retain(|_, batches| !batches.is_empty());
+        for (item, batch) in replacements {
+            self.add_batch(item, batch);
+        }
+        spoiled.sort();
+        spoiled
+    }
+
+    /// Total weight of everything held, saturating at `u32::MAX`.
+    pub fn weight(&self, items: &ItemDatabase) -> u32 {
+        self.iter()
+            .map(|(item, qty)| items.weight_of(item, qty))
+            .fold(0, u32::saturating_add)
+    }
+
+    /// Weight that can still be added, or `None` if there is no limit.
+    pub fn remaining_capacity(&self, items: &ItemDatabase) -> Option<u32> {
+        self.capacity
+            .map(|capacity| capacity.saturating_sub(self.weight(items)))
+    }
+
+    pub fn check_capacity(
+        &self,
+        item: ItemIndex,
+        amount: u32,
+        items: &ItemDatabase,
+    ) -> Result<(), InventoryError> {
+        let Some(capacity) = self.capacity else {
+            return Ok(());
+        };
+        let weight = self
+            .weight(items)
+            .saturating_add(items.weight_of(item, amount));
+        if weight > capacity {
+            Err(InventoryError::OverCapacity { capacity, weight })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
     }
 }
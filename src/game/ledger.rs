@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use super::item::{ItemDatabase, ItemIndex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionKind {
+    Buy,
+    Sell,
+    Gift,
+    Toll,
+    Tribute,
+    /// Guard wages paid for days on the road.
+    Wages,
+    /// The upfront fee for hiring guards.
+    Hire,
+    /// Goods taken by bandits. No gold changes hands.
+    Theft,
+    /// Goods lost or given away on the road. No gold changes hands.
+    Loss,
+}
+
+impl std::fmt::Display for TransactionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TransactionKind::Buy => "buy",
+            TransactionKind::Sell => "sell",
+            TransactionKind::Gift => "gift",
+            TransactionKind::Toll => "toll",
+            TransactionKind::Tribute => "tribute",
+            TransactionKind::Wages => "wages",
+            TransactionKind::Hire => "hire",
+            TransactionKind::Theft => "theft",
+            TransactionKind::Loss => "loss",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    /// Game day the transaction happened on.
+    pub day: u32,
+    pub kind: TransactionKind,
+    /// Goods that changed hands, if any.
+    pub goods: Option<(ItemIndex, u32)>,
+    /// Change to the player's gold. Negative when the player paid.
+    pub gold: i64,
+    /// Gold balance after the transaction.
+    pub balance: u32,
+    /// Gold plus the base value of the inventory after the transaction.
+    pub net_worth: u32,
+}
+
+/// Append-only record of every transaction the player made.
+#[derive(Debug, Default)]
+pub struct Ledger {
+    transactions: Vec<Transaction>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self {
+            transactions: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, transaction: Transaction) {
+        self.transactions.push(transaction);
+    }
+
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions
+    }
+
+    /// (day, net worth) pairs, one per transaction, for charting a run.
+    pub fn net_worth_history(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.transactions.iter().map(|t| (t.day, t.net_worth))
+    }
+
+    pub fn to_csv(&self, items: &ItemDatabase) -> String {
+        let mut out = "day,kind,item,quantity,gold,balance,net_worth".to_string();
+        for t in &self.transactions {
+            let (item, quantity) = match t.goods {
+                Some((item, quantity)) => (
+                    items.get_item(item).map_or("", |i| i.key.as_str()),
+                    quantity,
+                ),
+                None => ("", 0),
+            };
+            write!(
+                out,
+                "\n{},{},{},{},{},{},{}",
+                t.day, t.kind, item, quantity, t.gold, t.balance, t.net_worth
+            )
+            .unwrap();
+        }
+        out
+    }
+}
@@ -0,0 +1,234 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::graph::Graph;
+
+use super::{
+    city::{City, RoadData},
+    clock::Season,
+    item::{InventoryError, ItemDatabase, ItemIndex, ItemType},
+    ledger::TransactionKind,
+    player::PlayerState,
+    pricing::ItemEconomy,
+};
+
+/// Share of the demand level a city produces and consumes each day when the
+/// world file does not say otherwise.
+const DEFAULT_TURNOVER: f32 = 0.1;
+/// Demand assumed for items a city trades but does not stock.
+const DEFAULT_DEMAND: u32 = 10;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MarketData {
+    pub item: String,
+    #[serde(default)]
+    pub stock: u32,
+    /// Stock level at which the item sells for its base value. Defaults to the
+    /// starting stock.
+    pub demand: Option<f32>,
+    pub production: Option<f32>,
+    pub consumption: Option<f32>,
+}
+
+impl MarketData {
+    pub fn economy(&self) -> ItemEconomy {
+        let demand = self.demand.unwrap_or(self.stock.max(DEFAULT_DEMAND) as f32);
+        ItemEconomy::new(
+            demand,
+            self.production.unwrap_or(demand * DEFAULT_TURNOVER),
+            self.consumption.unwrap_or(demand * DEFAULT_TURNOVER),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeError {
+    NotTraded,
+    OutOfStock,
+    NotEnoughGold,
+    NotEnoughItems,
+    TooHeavy,
+}
+
+impl std::fmt::Display for TradeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TradeError::NotTraded => write!(f, "This market does not trade that item"),
+            TradeError::OutOfStock => write!(f, "The market does not have that many in stock"),
+            TradeError::NotEnoughGold => write!(f, "You cannot afford that"),
+            TradeError::NotEnoughItems => write!(f, "You do not have that many to sell"),
+            TradeError::TooHeavy => write!(f, "You cannot carry that much"),
+        }
+    }
+}
+
+/// The goods a city is willing to trade. Stock levels live in `City::inventory`.
+#[derive(Debug, Default)]
+pub struct Market {
+    listings: Vec<ItemIndex>,
+    economies: HashMap<ItemIndex, ItemEconomy>,
+    season: Season,
+}
+
+impl Market {
+    pub fn new() -> Self {
+        Self {
+            listings: Vec::new(),
+            economies: HashMap::new(),
+            season: Season::Spring,
+        }
+    }
+
+    pub fn add_listing(&mut self, item: ItemIndex, economy: ItemEconomy) {
+        if !self.trades(item) {
+            self.listings.push(item);
+        }
+        self.economies.insert(item, economy);
+    }
+
+    pub fn listings(&self) -> &[ItemIndex] {
+        &self.listings
+    }
+
+    pub fn trades(&self, item: ItemIndex) -> bool {
+        self.economies.contains_key(&item)
+    }
+
+    pub fn economy(&self, item: ItemIndex) -> Option<&ItemEconomy> {
+        self.economies.get(&item)
+    }
+
+    pub fn economy_mut(&mut self, item: ItemIndex) -> Option<&mut ItemEconomy> {
+        self.economies.get_mut(&item)
+    }
+
+    pub fn season(&self) -> Season {
+        self.season
+    }
+
+    pub fn set_season(&mut self, season: Season) {
+        self.season = season;
+    }
+
+    /// The economy prices are quoted from, with demand adjusted for the season.
+    fn pricing(&self, item: ItemIndex, item_type: &ItemType) -> Option<ItemEconomy> {
+        let economy = self.economies.get(&item)?;
+        Some(economy.in_season(item_type.demand_factor(self.season)))
+    }
+}
+
+/// Price of one unit bought from the city at its current stock level.
+pub fn buy_price(city: &City, items: &ItemDatabase, item: ItemIndex) -> Option<u32> {
+    let item_type = items.get_item(item)?;
+    let economy = city.market.pricing(item, item_type)?;
+    Some(economy.buy_price(item_type.value, city.inventory.quantity(item)))
+}
+
+/// Price of one unit sold to the city at its current stock level.
+pub fn sell_price(city: &City, items: &ItemDatabase, item: ItemIndex) -> Option<u32> {
+    let item_type = items.get_item(item)?;
+    let economy = city.market.pricing(item, item_type)?;
+    Some(economy.sell_price(item_type.value, city.inventory.quantity(item)))
+}
+
+/**
+    Moves `amount` of `item` from the city's stock into the player's inventory
+    and records the purchase in the player's ledger. Returns the total price paid.
+
+    City stock is never aged, since it turns over through consumption, so the
+    goods count as acquired on the day they are bought.
+*/
+pub fn buy(
+    city: &mut City,
+    player: &mut PlayerState,
+    items: &ItemDatabase,
+    item: ItemIndex,
+    amount: u32,
+    day: u32,
+) -> Result<u32, TradeError> {
+    let item_type = items.get_item(item).ok_or(TradeError::NotTraded)?;
+    let economy = city
+        .market
+        .pricing(item, item_type)
+        .ok_or(TradeError::NotTraded)?;
+    let stock = city.inventory.quantity(item);
+    if stock < amount {
+        return Err(TradeError::OutOfStock);
+    }
+    let total = economy.buy_total(item_type.value, stock, amount);
+    if player.gold < total {
+        return Err(TradeError::NotEnoughGold);
+    }
+    city.inventory
+        .transfer_fresh_to(&mut player.inventory, item, amount, items)
+        .map_err(|err| match err {
+            InventoryError::Insufficient { .. } => TradeError::OutOfStock,
+            InventoryError::OverCapacity { .. } => TradeError::TooHeavy,
+        })?;
+    player.transact(
+        day,
+        TransactionKind::Buy,
+        -(total as i64),
+        Some((item, amount)),
+        items,
+    );
+    Ok(total)
+}
+
+/**
+    Moves `amount` of `item` from the player's inventory into the city's stock
+    and records the sale in the player's ledger. Returns the total price received.
+*/
+pub fn sell(
+    city: &mut City,
+    player: &mut PlayerState,
+    items: &ItemDatabase,
+    item: ItemIndex,
+    amount: u32,
+    day: u32,
+) -> Result<u32, TradeError> {
+    let item_type = items.get_item(item).ok_or(TradeError::NotTraded)?;
+    let economy = city
+        .market
+        .pricing(item, item_type)
+        .ok_or(TradeError::NotTraded)?;
+    if player.inventory.quantity(item) < amount {
+        return Err(TradeError::NotEnoughItems);
+    }
+    let total = economy.sell_total(item_type.value, city.inventory.quantity(item), amount);
+    player
+        .inventory
+        .transfer_to(&mut city.inventory, item, amount, items)
+        .map_err(|_| TradeError::NotEnoughItems)?;
+    player.transact(
+        day,
+        TransactionKind::Sell,
+        total as i64,
+        Some((item, amount)),
+        items,
+    );
+    Ok(total)
+}
+
+/// Runs a city's production and consumption for `days`.
+pub fn restock(city: &mut City, days: f32) {
+    for (item, economy) in city.market.economies.iter_mut() {
+        let change = economy.tick(city.inventory.quantity(*item), days);
+        if change > 0 {
+            city.inventory.add_item(*item, change as u32);
+        } else if change < 0 {
+            // tick never drains more than the current stock
+            let _ = city
+                .inventory
+                .remove_item(*item, change.unsigned_abs() as u32);
+        }
+    }
+}
+
+/// Advances every market on the map by `days`, ending in `season`.
+pub fn advance_markets(map: &mut Graph<City, RoadData>, days: f32, season: Season) {
+    for city in map.nodes_mut() {
+        restock(city, days);
+        city.market.set_season(season);
+    }
+}
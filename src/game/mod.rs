@@ -1,4 +1,14 @@
+pub mod bandit;
 pub mod city;
+pub mod clock;
+pub mod crafting;
+pub mod event;
+pub mod food;
 pub mod item;
+pub mod ledger;
+pub mod market;
 pub mod player;
+pub mod pricing;
+pub mod route;
+pub mod save;
 pub mod world;
@@ -2,20 +2,34 @@ use std::collections::HashSet;
 
 use crate::graph::NodeIndex;
 
-use super::item::Inventory;
+use super::{
+    food::Hunger,
+    item::{Inventory, ItemDatabase, ItemIndex},
+    ledger::{Ledger, Transaction, TransactionKind},
+};
 
 pub struct PlayerState {
     pub position: NodeIndex,
+    pub gold: u32,
     pub inventory: Inventory,
+    /// Hired guards travelling with the player.
+    pub guards: u32,
+    /// Days in a row the caravan has gone without a full meal.
+    pub hunger_days: u32,
     pub visited_places: HashSet<NodeIndex>,
+    pub ledger: Ledger,
 }
 
 impl PlayerState {
-    pub fn new(position: NodeIndex) -> Self {
+    pub fn new(position: NodeIndex, gold: u32) -> Self {
         Self {
             position,
+            gold,
             inventory: Inventory::new(),
+            guards: 0,
+            hunger_days: 0,
             visited_places: HashSet::new(),
+            ledger: Ledger::new(),
         }
     }
 
@@ -23,4 +37,49 @@ impl PlayerState {
         self.position = node;
         self.visited_places.insert(node);
     }
+
+    /// Everyone who eats on the road: the player and their guards.
+    pub fn caravan_size(&self) -> u32 {
+        1 + self.guards
+    }
+
+    pub fn hunger(&self) -> Hunger {
+        Hunger::from_days(self.hunger_days)
+    }
+
+    /// Gold plus the base value of everything the player carries.
+    pub fn net_worth(&self, items: &ItemDatabase) -> u32 {
+        self.inventory
+            .iter()
+            .map(|(item, qty)| items.get_item(item).map_or(0, |i| i.value) * qty)
+            .sum::<u32>()
+            + self.gold
+    }
+
+    /**
+        Applies a change in gold and records it in the ledger. Any goods should
+        already have been moved so the recorded net worth is accurate.
+        Returns the new balance, or `None` if the player cannot afford it.
+    */
+    pub fn transact(
+        &mut self,
+        day: u32,
+        kind: TransactionKind,
+        gold: i64,
+        goods: Option<(ItemIndex, u32)>,
+        items: &ItemDatabase,
+    ) -> Option<u32> {
+        let balance = u32::try_from(self.gold as i64 + gold).ok()?;
+        self.gold = balance;
+        let net_worth = self.net_worth(items);
+        self.ledger.record(Transaction {
+            day,
+            kind,
+            goods,
+            gold,
+            balance,
+            net_worth,
+        });
+        Some(balance)
+    }
 }
@@ -0,0 +1,176 @@
+/// How strongly the price reacts to scarcity. At 0.5 a quarter of the demanded
+/// stock doubles the price.
+pub const ELASTICITY: f32 = 0.5;
+/// Prices never fall below this fraction of the base value.
+pub const MIN_PRICE_FACTOR: f32 = 0.25;
+/// Prices never rise above this multiple of the base value.
+pub const MAX_PRICE_FACTOR: f32 = 4.0;
+/// Gap between the mid price and what the market charges or pays.
+pub const SPREAD: f32 = 0.1;
+
+/**
+    Supply and demand state for one item in one city. Supply is the city's stock,
+    which is passed in rather than stored so the market inventory stays the single
+    source of truth.
+
+    Stock follows `d(stock)/dt = production - consumption * stock / demand`, so it
+    drifts towards `equilibrium()` after the player buys or sells.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemEconomy {
+    /// Stock level at which the item sells for its base value.
+    pub demand: f32,
+    /// Units produced per day.
+    pub production: f32,
+    /// Units consumed per day while stock sits at the demand level.
+    pub consumption: f32,
+    /// Fractional stock change that has not yet added up to a whole unit.
+    pub carry: f32,
+}
+
+impl ItemEconomy {
+    pub fn new(demand: f32, production: f32, consumption: f32) -> Self {
+        Self {
+            demand: demand.max(1.0),
+            production: production.max(0.0),
+            consumption: consumption.max(0.0),
+            carry: 0.0,
+        }
+    }
+
+    /**
+        The same economy with demand scaled for the season. Only prices use this;
+        restocking keeps the base demand so that the seasonal swing is not simply
+        absorbed by stock drifting to a new equilibrium.
+    */
+    pub fn in_season(&self, factor: f32) -> Self {
+        Self {
+            demand: (self.demand * factor).max(1.0),
+            ..*self
+        }
+    }
+
+    /// Stock level the market settles at if left alone.
+    pub fn equilibrium(&self) -> f32 {
+        if self.consumption == 0.0 {
+            f32::INFINITY
+        } else {
+            self.demand * self.production / self.consumption
+        }
+    }
+
+    /// Price before the spread is applied.
+    pub fn mid_price(&self, base: u32, supply: u32) -> f32 {
+        let scarcity = self.demand / supply.max(1) as f32;
+        let factor = scarcity
+            .powf(ELASTICITY)
+            .clamp(MIN_PRICE_FACTOR, MAX_PRICE_FACTOR);
+        base as f32 * factor
+    }
+
+    /// What the player pays for one unit.
+    pub fn buy_price(&self, base: u32, supply: u32) -> u32 {
+        ((self.mid_price(base, supply) * (1.0 + SPREAD)).ceil() as u32).max(1)
+    }
+
+    /// What the player receives for one unit.
+    pub fn sell_price(&self, base: u32, supply: u32) -> u32 {
+        (self.mid_price(base, supply) * (1.0 - SPREAD)).floor() as u32
+    }
+
+    /// Total cost of buying `amount` units, with the price rising as stock runs down.
+    pub fn buy_total(&self, base: u32, supply: u32, amount: u32) -> u32 {
+        (0..amount.min(supply))
+            .map(|bought| self.buy_price(base, supply - bought))
+            .sum()
+    }
+
+    /// Total received for selling `amount` units, with the price falling as stock builds.
+    pub fn sell_total(&self, base: u32, supply: u32, amount: u32) -> u32 {
+        (0..amount)
+            .map(|sold| self.sell_price(base, supply + sold))
+            .sum()
+    }
+
+    /**
+        Advances production and consumption by `days` and returns the whole-unit
+        change in stock. Fractions are carried over to the next tick.
+    */
+    pub fn tick(&mut self, supply: u32, days: f32) -> i64 {
+        let supply = supply as f32;
+        let target = if self.consumption == 0.0 {
+            supply + self.production * days
+        } else {
+            let rate = self.consumption / self.demand;
+            let equilibrium = self.equilibrium();
+            equilibrium + (supply - equilibrium) * (-rate * days).exp()
+        };
+        let change = target - supply + self.carry;
+        let whole = change.trunc();
+        self.carry = change - whole;
+        (whole as i64).max(-(supply as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_at_base_value_when_stock_meets_demand() {
+        let economy = ItemEconomy::new(50.0, 5.0, 5.0);
+        assert_eq!(economy.mid_price(10, 50), 10.0);
+        assert_eq!(economy.buy_price(10, 50), 11);
+        assert_eq!(economy.sell_price(10, 50), 9);
+    }
+
+    #[test]
+    fn price_curve_rises_with_scarcity_and_is_clamped() {
+        let economy = ItemEconomy::new(100.0, 0.0, 0.0);
+        let mut last = 0.0;
+        for supply in (1..=400).rev() {
+            let price = economy.mid_price(20, supply);
+            assert!(price >= last, "price fell as stock shrank to {}", supply);
+            last = price;
+        }
+        assert_eq!(economy.mid_price(20, 25), 40.0);
+        assert_eq!(economy.mid_price(20, 0), 20.0 * MAX_PRICE_FACTOR);
+        assert_eq!(economy.mid_price(20, 100_000), 20.0 * MIN_PRICE_FACTOR);
+    }
+
+    #[test]
+    fn buying_costs_more_than_selling_back() {
+        let economy = ItemEconomy::new(40.0, 4.0, 4.0);
+        let paid = economy.buy_total(25, 40, 10);
+        let received = economy.sell_total(25, 30, 10);
+        assert!(paid > received);
+        assert!(paid > economy.buy_price(25, 40) * 9);
+    }
+
+    #[test]
+    fn stock_drifts_back_to_equilibrium() {
+        let mut economy = ItemEconomy::new(60.0, 6.0, 6.0);
+        let mut supply = 10;
+        for _ in 0..120 {
+            supply = (supply as i64 + economy.tick(supply, 1.0)) as u32;
+        }
+        assert!((58..=60).contains(&supply), "supply settled at {}", supply);
+
+        let mut supply = 200;
+        for _ in 0..120 {
+            supply = (supply as i64 + economy.tick(supply, 1.0)) as u32;
+        }
+        assert!((60..=62).contains(&supply), "supply settled at {}", supply);
+    }
+
+    #[test]
+    fn pure_consumers_run_dry() {
+        let mut economy = ItemEconomy::new(20.0, 0.0, 2.0);
+        let mut supply = 15;
+        for _ in 0..200 {
+            supply = (supply as i64 + economy.tick(supply, 1.0)) as u32;
+        }
+        assert_eq!(supply, 0);
+        assert_eq!(economy.buy_price(10, 0), 44);
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops::Add;
+
+use crate::graph::{Cost, NodeIndex, Path, Search};
+
+use super::{
+    bandit::{AMBUSH_MILES, GUARD_WAGE},
+    city::RoadData,
+    clock::HOURS_PER_DAY,
+    item::ItemDatabase,
+    player::PlayerState,
+    world::World,
+};
+
+/// Miles a fed caravan with an empty cart covers in a day.
+pub const MILES_PER_DAY: u32 = 25;
+/// Fraction of the pace lost with a fully loaded cart.
+pub const LOADED_SLOWDOWN: f32 = 0.3;
+
+/// Miles the caravan covers in a day, slowed by hunger and a heavy cart.
+pub fn miles_per_day(player: &PlayerState, items: &ItemDatabase) -> f32 {
+    let load = match player.inventory.capacity {
+        Some(capacity) if capacity > 0 => {
+            (player.inventory.weight(items) as f32 / capacity as f32).min(1.0)
+        }
+        _ => 0.0,
+    };
+    MILES_PER_DAY as f32 * player.hunger().speed_factor() * (1.0 - LOADED_SLOWDOWN * load)
+}
+
+/// Hours the caravan would take to travel `road` at its current pace.
+pub fn travel_hours(road: &RoadData, player: &PlayerState, items: &ItemDatabase) -> f32 {
+    road.distance as f32 * HOURS_PER_DAY as f32 / miles_per_day(player, items)
+}
+
+/// What the route planner tries to keep down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteKind {
+    /// Fewest hours on the road.
+    Fastest,
+    /// Fewest expected ambushes.
+    Safest,
+    /// Least gold spent on tolls and guard wages.
+    Cheapest,
+}
+
+impl RouteKind {
+    pub const ALL: [RouteKind; 3] = [RouteKind::Fastest, RouteKind::Safest, RouteKind::Cheapest];
+
+    /// What taking `road` costs the player by this measure.
+    pub fn road_cost(
+        &self,
+        road: &RoadData,
+        player: &PlayerState,
+        items: &ItemDatabase,
+    ) -> RouteCost {
+        let hours = travel_hours(road, player, items);
+        let score = match self {
+            RouteKind::Fastest => hours,
+            RouteKind::Safest => road.distance as f32 * road.danger / AMBUSH_MILES,
+            RouteKind::Cheapest => {
+                let wages = (player.guards * GUARD_WAGE) as f32 * hours / HOURS_PER_DAY as f32;
+                road.toll as f32 + wages
+            }
+        };
+        RouteCost { score, hours }
+    }
+}
+
+impl std::fmt::Display for RouteKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RouteKind::Fastest => write!(f, "fastest"),
+            RouteKind::Safest => write!(f, "safest"),
+            RouteKind::Cheapest => write!(f, "cheapest"),
+        }
+    }
+}
+
+/// A route's score by its `RouteKind`, with travel time breaking ties.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct RouteCost {
+    pub score: f32,
+    pub hours: f32,
+}
+
+impl Add for RouteCost {
+    type Output = RouteCost;
+
+    fn add(self, other: RouteCost) -> RouteCost {
+        RouteCost {
+            score: self.score + other.score,
+            hours: self.hours + other.hours,
+        }
+    }
+}
+
+impl Cost for RouteCost {
+    type Total = RouteCost;
+
+    fn total(self) -> RouteCost {
+        self
+    }
+}
+
+/// The best route for the player from where they are to `to`, by `kind`.
+pub fn plan_route(
+    world: &World,
+    player: &PlayerState,
+    to: NodeIndex,
+    kind: RouteKind,
+) -> Option<Path<RouteCost>> {
+    world.map.dijkstra_by(player.position, to, |road, _, _| {
+        kind.road_cost(road, player, &world.items)
+    })
+}
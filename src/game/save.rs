@@ -0,0 +1,390 @@
+use fastrand::Rng;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::graph::NodeIndex;
+
+use super::{
+    clock::Clock,
+    item::{Batch, Inventory, ItemDatabase},
+    ledger::{Transaction, TransactionKind},
+    player::PlayerState,
+    world::World,
+};
+
+/// Bumped whenever the save layout changes incompatibly.
+pub const SAVE_VERSION: u32 = 2;
+/// Oldest save layout that can still be loaded.
+pub const MIN_SAVE_VERSION: u32 = 1;
+pub const SAVE_DIR: &str = "saves";
+
+/**
+    A snapshot of a run. Cities and items are stored by name and key rather than
+    by `NodeIndex`/`ItemIndex` so saves keep working when `world.toml` is reordered.
+*/
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SaveData {
+    pub version: u32,
+    pub day: u32,
+    #[serde(default)]
+    pub hour: u32,
+    /// Hex encoded, since toml integers cannot hold every u64.
+    pub rng_state: String,
+    pub player: PlayerSave,
+    pub cities: Vec<CitySave>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlayerSave {
+    pub position: String,
+    pub gold: u32,
+    #[serde(default)]
+    pub guards: u32,
+    #[serde(default)]
+    pub hunger_days: u32,
+    #[serde(deserialize_with = "batches_or_totals")]
+    pub inventory: Vec<BatchSave>,
+    pub visited_places: Vec<String>,
+    pub ledger: Vec<TransactionSave>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchSave {
+    pub item: String,
+    pub quantity: u32,
+    pub acquired: u32,
+}
+
+/// Version 1 saves stored plain totals, which load as one batch acquired on day 0.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InventorySave {
+    Batches(Vec<BatchSave>),
+    Totals(BTreeMap<String, u32>),
+}
+
+fn batches_or_totals<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<BatchSave>, D::Error> {
+    Ok(match InventorySave::deserialize(deserializer)? {
+        InventorySave::Batches(batches) => batches,
+        InventorySave::Totals(totals) => totals
+            .into_iter()
+            .map(|(item, quantity)| BatchSave {
+                item,
+                quantity,
+                acquired: 0,
+            })
+            .collect(),
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransactionSave {
+    pub day: u32,
+    pub kind: TransactionKind,
+    pub item: Option<String>,
+    #[serde(default)]
+    pub quantity: u32,
+    pub gold: i64,
+    pub balance: u32,
+    pub net_worth: u32,
+}
+
+/**
+    A city's stock plus the fractional progress of its economy. Progress for
+    recipes or items the city no longer runs or trades is dropped on load.
+*/
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CitySave {
+    pub name: String,
+    pub inventory: BTreeMap<String, u32>,
+    /// Days into the current batch, by recipe key.
+    #[serde(default)]
+    pub production: BTreeMap<String, f32>,
+    /// Restocking not yet added up to a whole unit, by item key.
+    #[serde(default)]
+    pub restock_carry: BTreeMap<String, f32>,
+}
+
+/// Everything `SaveData::restore` hands back besides the updated city stock.
+pub struct RestoredGame {
+    pub player: PlayerState,
+    pub clock: Clock,
+    pub rng: Rng,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Serialize(toml::ser::Error),
+    Parse(toml::de::Error),
+    Version(u32),
+    UnknownCity(String),
+    UnknownItem(String),
+    Corrupt(String),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "Could not access save file: {}", err),
+            SaveError::Serialize(err) => write!(f, "Could not write save: {}", err),
+            SaveError::Parse(err) => write!(f, "Could not read save: {}", err),
+            SaveError::Version(version) => write!(
+                f,
+                "Save version {} is not supported (expected {} to {})",
+                version, MIN_SAVE_VERSION, SAVE_VERSION
+            ),
+            SaveError::UnknownCity(name) => write!(f, "Save refers to unknown city {}", name),
+            SaveError::UnknownItem(key) => write!(f, "Save refers to unknown item {}", key),
+            SaveError::Corrupt(reason) => write!(f, "Save is corrupt: {}", reason),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl From<toml::ser::Error> for SaveError {
+    fn from(err: toml::ser::Error) -> Self {
+        SaveError::Serialize(err)
+    }
+}
+
+impl From<toml::de::Error> for SaveError {
+    fn from(err: toml::de::Error) -> Self {
+        SaveError::Parse(err)
+    }
+}
+
+fn inventory_to_keys(inventory: &Inventory, items: &ItemDatabase) -> BTreeMap<String, u32> {
+    inventory
+        .iter()
+        .filter(|(_, qty)| *qty > 0)
+        .filter_map(|(item, qty)| Some((items.get_item(item)?.key.clone(), qty)))
+        .collect()
+}
+
+fn inventory_to_batches(inventory: &Inventory, items: &ItemDatabase) -> Vec<BatchSave> {
+    let mut batches: Vec<BatchSave> = inventory
+        .iter()
+        .filter_map(|(item, _)| Some((item, &items.get_item(item)?.key)))
+        .flat_map(|(item, key)| {
+            inventory.batches(item).iter().map(|batch| BatchSave {
+                item: key.clone(),
+                quantity: batch.quantity,
+                acquired: batch.acquired,
+            })
+        })
+        .collect();
+    batches.sort_by(|a, b| (&a.item, a.acquired).cmp(&(&b.item, b.acquired)));
+    batches
+}
+
+fn inventory_from_batches(
+    saved: &[BatchSave],
+    items: &ItemDatabase,
+) -> Result<Inventory, SaveError> {
+    let mut inventory = Inventory::new();
+    for batch in saved {
+        let item = items
+            .get_index(&batch.item)
+            .ok_or_else(|| SaveError::UnknownItem(batch.item.clone()))?;
+        inventory.add_batch(
+            *item,
+            Batch {
+                quantity: batch.quantity,
+                acquired: batch.acquired,
+            },
+        );
+    }
+    Ok(inventory)
+}
+
+fn inventory_from_keys(
+    saved: &BTreeMap<String, u32>,
+    items: &ItemDatabase,
+) -> Result<Inventory, SaveError> {
+    let mut inventory = Inventory::new();
+    for (key, qty) in saved {
+        let item = items
+            .get_index(key)
+            .ok_or_else(|| SaveError::UnknownItem(key.clone()))?;
+        inventory.add_item(*item, *qty);
+    }
+    Ok(inventory)
+}
+
+impl SaveData {
+    pub fn capture(world: &World, player: &PlayerState, clock: Clock, rng: &Rng) -> SaveData {
+        let (map, items) = (&world.map, &world.items);
+        let city_name = |node: NodeIndex| map.get_node(node).unwrap().name.clone();
+        let mut visited_places: Vec<String> = player
+            .visited_places
+            .iter()
+            .map(|n| city_name(*n))
+            .collect();
+        visited_places.sort();
+
+        let ledger = player
+            .ledger
+            .transactions()
+            .iter()
+            .map(|t| TransactionSave {
+                day: t.day,
+                kind: t.kind,
+                item: t
+                    .goods
+                    .and_then(|(item, _)| Some(items.get_item(item)?.key.clone())),
+                quantity: t.goods.map_or(0, |(_, qty)| qty),
+                gold: t.gold,
+                balance: t.balance,
+                net_worth: t.net_worth,
+            })
+            .collect();
+
+        SaveData {
+            version: SAVE_VERSION,
+            day: clock.day(),
+            hour: clock.hour(),
+            rng_state: format!("{:016x}", rng.get_seed()),
+            player: PlayerSave {
+                position: city_name(player.position),
+                gold: player.gold,
+                guards: player.guards,
+                hunger_days: player.hunger_days,
+                inventory: inventory_to_batches(&player.inventory, items),
+                visited_places,
+                ledger,
+            },
+            cities: map
+                .nodes()
+                .map(|(_, city)| CitySave {
+                    name: city.name.clone(),
+                    inventory: inventory_to_keys(&city.inventory, items),
+                    production: city
+                        .production
+                        .iter()
+                        .filter_map(|production| {
+                            let recipe = world.recipes.get_recipe(production.recipe)?;
+                            Some((recipe.key.clone(), production.elapsed))
+                        })
+                        .collect(),
+                    restock_carry: city
+                        .market
+                        .listings()
+                        .iter()
+                        .filter_map(|item| {
+                            let carry = city.market.economy(*item)?.carry;
+                            Some((items.get_item(*item)?.key.clone(), carry))
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /**
+        Resolves every stored name against the current world and, only once all of
+        them are known, overwrites the city stock in `world`.
+    */
+    pub fn restore(&self, world: &mut World) -> Result<RestoredGame, SaveError> {
+        if !(MIN_SAVE_VERSION..=SAVE_VERSION).contains(&self.version) {
+            return Err(SaveError::Version(self.version));
+        }
+        let items = &world.items;
+        let find_city = |name: &str| {
+            world
+                .city_index(name)
+                .ok_or_else(|| SaveError::UnknownCity(name.to_string()))
+        };
+
+        let rng_state = u64::from_str_radix(&self.rng_state, 16)
+            .map_err(|_| SaveError::Corrupt(format!("bad rng state {}", self.rng_state)))?;
+
+        let mut player = PlayerState::new(find_city(&self.player.position)?, self.player.gold);
+        let clock = Clock::at(self.day, self.hour);
+        player.inventory = inventory_from_batches(&self.player.inventory, items)?;
+        player.inventory.day = clock.day();
+        player.inventory.capacity = world.carry_capacity;
+        player.guards = self.player.guards;
+        player.hunger_days = self.player.hunger_days;
+        for name in &self.player.visited_places {
+            player.visited_places.insert(find_city(name)?);
+        }
+        for t in &self.player.ledger {
+            let goods = match &t.item {
+                Some(key) => Some((
+                    *items
+                        .get_index(key)
+                        .ok_or_else(|| SaveError::UnknownItem(key.clone()))?,
+                    t.quantity,
+                )),
+                None => None,
+            };
+            player.ledger.record(Transaction {
+                day: t.day,
+                kind: t.kind,
+                goods,
+                gold: t.gold,
+                balance: t.balance,
+                net_worth: t.net_worth,
+            });
+        }
+
+        let mut stock = Vec::new();
+        for city in &self.cities {
+            stock.push((
+                find_city(&city.name)?,
+                inventory_from_keys(&city.inventory, items)?,
+            ));
+        }
+        for ((node, mut inventory), saved) in stock.into_iter().zip(&self.cities) {
+            let city = world.map.get_node_mut(node).unwrap();
+            inventory.day = clock.day();
+            city.inventory = inventory;
+            city.market.set_season(clock.season());
+            for production in city.production.iter_mut() {
+                let key = &world.recipes.get_recipe(production.recipe).unwrap().key;
+                production.elapsed = saved.production.get(key).copied().unwrap_or(0.0);
+            }
+            for (key, carry) in &saved.restock_carry {
+                let economy = world
+                    .items
+                    .get_index(key)
+                    .and_then(|item| city.market.economy_mut(*item));
+                if let Some(economy) = economy {
+                    economy.carry = *carry;
+                }
+            }
+        }
+
+        Ok(RestoredGame {
+            player,
+            clock,
+            rng: Rng::with_seed(rng_state),
+        })
+    }
+}
+
+pub fn save_path(slot: &str) -> PathBuf {
+    PathBuf::from(SAVE_DIR).join(format!("{}.toml", slot))
+}
+
+pub fn write_save(slot: &str, save: &SaveData) -> Result<PathBuf, SaveError> {
+    let path = save_path(slot);
+    fs::create_dir_all(SAVE_DIR)?;
+    fs::write(&path, toml::to_string(save)?)?;
+    Ok(path)
+}
+
+pub fn read_save(slot: &str) -> Result<SaveData, SaveError> {
+    let contents = fs::read_to_string(save_path(slot))?;
+    Ok(toml::from_str(&contents)?)
+}
@@ -1,14 +1,655 @@
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 
-use super::{city::CityData, item::ItemType};
+use crate::graph::{Connectivity, DistanceMatrix, Edge, EdgeIndex, Graph, NodeIndex, Search};
+
+use super::{
+    city::{City, CityData, RoadData},
+    crafting::{IngredientData, Production, Recipe, RecipeBook, RecipeData},
+    event::{Event, EventBook, EventData},
+    item::{ItemDatabase, ItemIndex, ItemTag, ItemType},
+    market,
+    player::PlayerState,
+};
 
 #[derive(Deserialize, Debug)]
 pub struct WorldData {
+    #[serde(default)]
     pub starting_position: String,
+    #[serde(default = "default_starting_gold")]
+    pub starting_gold: u32,
+    /// Total item weight the player can carry. Unlimited when not set.
+    #[serde(default)]
+    pub carry_capacity: Option<u32>,
     pub cities: Vec<CityData>,
     pub items: Vec<ItemType>,
+    #[serde(default)]
+    pub recipes: Vec<RecipeData>,
+    #[serde(default)]
+    pub events: Vec<EventData>,
+}
+
+fn default_starting_gold() -> u32 {
+    100
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorldError {
+    /// The file is not valid toml or does not match the world layout.
+    Parse {
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    UnknownRoadTarget {
+        city: String,
+        road: String,
+        to: String,
+    },
+    ZeroDistanceRoad {
+        city: String,
+        road: String,
+    },
+    InvalidDanger {
+        city: String,
+        road: String,
+    },
+    DuplicateCity(String),
+    DuplicateItem(String),
+    EdibleWithoutNutrition(String),
+    UnknownSpoilage {
+        item: String,
+        into: String,
+    },
+    /// Items that spoil into one another and so never run out, in spoiling order.
+    SpoilageCycle(Vec<String>),
+    UnknownMarketItem {
+        city: String,
+        item: String,
+    },
+    DuplicateRecipe(String),
+    UnknownRecipeItem {
+        recipe: String,
+        item: String,
+    },
+    UncraftibleOutput {
+        recipe: String,
+        item: String,
+    },
+    UnknownRecipeCity {
+        recipe: String,
+        city: String,
+    },
+    UnknownCityRecipe {
+        city: String,
+        recipe: String,
+    },
+    DuplicateEvent(String),
+    EventWithoutChoices(String),
+    UnknownEventItem {
+        event: String,
+        item: String,
+    },
+    UnknownEventRoad {
+        event: String,
+        road: String,
+    },
+    UnknownEventRegion {
+        event: String,
+        region: String,
+    },
+    UnreachableCity(String),
+    MissingStartingPosition,
+    UnknownStartingPosition(String),
+}
+
+impl WorldError {
+    /// Converts a toml error, using `source` to turn its byte span into a line and column.
+    pub fn from_toml(err: &toml::de::Error, source: &str) -> WorldError {
+        let (line, column) = match err.span() {
+            Some(span) => {
+                let before = &source[..span.start.min(source.len())];
+                let line = before.matches('\n').count() + 1;
+                let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+                (Some(line), Some(column))
+            }
+            None => (None, None),
+        };
+        WorldError::Parse {
+            message: err.message().to_string(),
+            line,
+            column,
+        }
+    }
+}
+
+impl std::fmt::Display for WorldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorldError::Parse {
+                message,
+                line: Some(line),
+                column: Some(column),
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            WorldError::Parse { message, .. } => write!(f, "{}", message),
+            WorldError::UnknownRoadTarget { city, road, to } => {
+                write!(f, "{} in {} leads to unknown city {}", road, city, to)
+            }
+            WorldError::ZeroDistanceRoad { city, road } => {
+                write!(f, "{} in {} has a distance of 0", road, city)
+            }
+            WorldError::InvalidDanger { city, road } => write!(
+                f,
+                "{} in {} has a danger rating outside 0.0 to 1.0",
+                road, city
+            ),
+            WorldError::DuplicateCity(name) => write!(f, "City {} is declared twice", name),
+            WorldError::DuplicateItem(key) => write!(f, "Item {} is declared twice", key),
+            WorldError::EdibleWithoutNutrition(key) => {
+                write!(f, "Item {} is tagged Edible but has no nutrition", key)
+            }
+            WorldError::UnknownSpoilage { item, into } => {
+                write!(f, "Item {} spoils into unknown item {}", item, into)
+            }
+            WorldError::SpoilageCycle(items) => {
+                write!(
+                    f,
+                    "Items spoil into each other forever: {}",
+                    items.join(" -> ")
+                )
+            }
+            WorldError::UnknownMarketItem { city, item } => {
+                write!(f, "The {} market trades unknown item {}", city, item)
+            }
+            WorldError::DuplicateRecipe(key) => write!(f, "Recipe {} is declared twice", key),
+            WorldError::UnknownRecipeItem { recipe, item } => {
+                write!(f, "Recipe {} uses unknown item {}", recipe, item)
+            }
+            WorldError::UncraftibleOutput { recipe, item } => write!(
+                f,
+                "Recipe {} makes {} which is not tagged Craftible",
+                recipe, item
+            ),
+            WorldError::UnknownRecipeCity { recipe, city } => {
+                write!(f, "Recipe {} requires unknown city {}", recipe, city)
+            }
+            WorldError::UnknownCityRecipe { city, recipe } => {
+                write!(f, "{} runs unknown recipe {}", city, recipe)
+            }
+            WorldError::DuplicateEvent(key) => write!(f, "Event {} is declared twice", key),
+            WorldError::EventWithoutChoices(key) => write!(f, "Event {} has no choices", key),
+            WorldError::UnknownEventItem { event, item } => {
+                write!(f, "Event {} uses unknown item {}", event, item)
+            }
+            WorldError::UnknownEventRoad { event, road } => {
+                write!(f, "Event {} refers to unknown road {}", event, road)
+            }
+            WorldError::UnknownEventRegion { event, region } => {
+                write!(f, "Event {} refers to unknown region {}", event, region)
+            }
+            WorldError::UnreachableCity(name) => {
+                write!(f, "{} cannot be reached from the starting position", name)
+            }
+            WorldError::MissingStartingPosition => write!(f, "starting_position is not set"),
+            WorldError::UnknownStartingPosition(name) => {
+                write!(f, "starting_position names unknown city {}", name)
+            }
+        }
+    }
 }
 
 pub fn deserialize_world(serialized_world: &str) -> Result<WorldData, toml::de::Error> {
     toml::from_str::<WorldData>(serialized_world)
 }
+
+/// Deserializes and validates a world file, reporting every problem found.
+pub fn check_world(serialized_world: &str) -> Result<WorldData, Vec<WorldError>> {
+    let world = deserialize_world(serialized_world)
+        .map_err(|err| vec![WorldError::from_toml(&err, serialized_world)])?;
+    validate_world(&world)?;
+    Ok(world)
+}
+
+/// Roads only need to connect cities, and items what they spoil into, for the structure checks.
+struct Link;
+impl Edge for Link {}
+
+pub fn validate_world(world: &WorldData) -> Result<(), Vec<WorldError>> {
+    let mut errors = Vec::new();
+
+    let mut cities = Graph::<&str, Link>::new();
+    let mut city_nodes = HashMap::new();
+    for city in &world.cities {
+        if city_nodes.contains_key(city.name.as_str()) {
+            errors.push(WorldError::DuplicateCity(city.name.clone()));
+        } else {
+            city_nodes.insert(city.name.as_str(), cities.insert_node(&city.name));
+        }
+    }
+
+    let mut item_keys = HashMap::new();
+    for item in &world.items {
+        if item_keys.insert(item.key.as_str(), item).is_some() {
+            errors.push(WorldError::DuplicateItem(item.key.clone()));
+        }
+        if item.has_tag(ItemTag::Edible) && item.nutrition == 0 {
+            errors.push(WorldError::EdibleWithoutNutrition(item.key.clone()));
+        }
+    }
+
+    let mut spoilage = Graph::<&str, Link>::new();
+    let mut item_nodes = HashMap::new();
+    for item in &world.items {
+        let key = item.key.as_str();
+        item_nodes
+            .entry(key)
+            .or_insert_with(|| spoilage.insert_node(key));
+    }
+    for item in &world.items {
+        if let Some(into) = &item.spoils_into {
+            match item_nodes.get(into.as_str()) {
+                Some(to) => {
+                    spoilage.insert_edge(Link, item_nodes[item.key.as_str()], *to);
+                }
+                None => errors.push(WorldError::UnknownSpoilage {
+                    item: item.key.clone(),
+                    into: into.clone(),
+                }),
+            }
+        }
+    }
+    if let Some(cycle) = spoilage.find_cycle() {
+        let keys = cycle
+            .nodes
+            .iter()
+            .map(|node| spoilage.get_node(*node).unwrap());
+        errors.push(WorldError::SpoilageCycle(
+            keys.map(|key| key.to_string()).collect(),
+        ));
+    }
+
+    let mut recipe_keys = HashSet::new();
+    for recipe in &world.recipes {
+        if !recipe_keys.insert(recipe.key.as_str()) {
+            errors.push(WorldError::DuplicateRecipe(recipe.key.clone()));
+        }
+        for ingredient in recipe.inputs.iter().chain(&recipe.outputs) {
+            if !item_keys.contains_key(ingredient.item.as_str()) {
+                errors.push(WorldError::UnknownRecipeItem {
+                    recipe: recipe.key.clone(),
+                    item: ingredient.item.clone(),
+                });
+            }
+        }
+        for output in &recipe.outputs {
+            match item_keys.get(output.item.as_str()) {
+                Some(item) if !item.has_tag(ItemTag::Craftible) => {
+                    errors.push(WorldError::UncraftibleOutput {
+                        recipe: recipe.key.clone(),
+                        item: output.item.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        if let Some(city) = &recipe.city {
+            if !city_nodes.contains_key(city.as_str()) {
+                errors.push(WorldError::UnknownRecipeCity {
+                    recipe: recipe.key.clone(),
+                    city: city.clone(),
+                });
+            }
+        }
+    }
+
+    for city in &world.cities {
+        for road in &city.roads {
+            if road.distance == 0 {
+                errors.push(WorldError::ZeroDistanceRoad {
+                    city: city.name.clone(),
+                    road: road.name.clone(),
+                });
+            }
+            if !(0.0..=1.0).contains(&road.danger) {
+                errors.push(WorldError::InvalidDanger {
+                    city: city.name.clone(),
+                    road: road.name.clone(),
+                });
+            }
+            match city_nodes.get(road.to.as_str()) {
+                Some(to) => {
+                    cities.insert_edge_undirected(Link, city_nodes[city.name.as_str()], *to);
+                }
+                None => errors.push(WorldError::UnknownRoadTarget {
+                    city: city.name.clone(),
+                    road: road.name.clone(),
+                    to: road.to.clone(),
+                }),
+            }
+        }
+        for listing in &city.market {
+            if !item_keys.contains_key(listing.item.as_str()) {
+                errors.push(WorldError::UnknownMarketItem {
+                    city: city.name.clone(),
+                    item: listing.item.clone(),
+                });
+            }
+        }
+        for recipe in &city.recipes {
+            if !recipe_keys.contains(recipe.as_str()) {
+                errors.push(WorldError::UnknownCityRecipe {
+                    city: city.name.clone(),
+                    recipe: recipe.clone(),
+                });
+            }
+        }
+    }
+
+    let road_names: HashSet<&str> = world
+        .cities
+        .iter()
+        .flat_map(|c| c.roads.iter().map(|r| r.name.as_str()))
+        .collect();
+    let regions: HashSet<&str> = world
+        .cities
+        .iter()
+        .filter_map(|c| c.region.as_deref())
+        .collect();
+    let mut event_keys = HashSet::new();
+    for event in &world.events {
+        if !event_keys.insert(event.key.as_str()) {
+            errors.push(WorldError::DuplicateEvent(event.key.clone()));
+        }
+        if event.choices.is_empty() {
+            errors.push(WorldError::EventWithoutChoices(event.key.clone()));
+        }
+        for item in event.item_keys() {
+            if !item_keys.contains_key(item) {
+                errors.push(WorldError::UnknownEventItem {
+                    event: event.key.clone(),
+                    item: item.to_string(),
+                });
+            }
+        }
+        for road in &event.conditions.roads {
+            if !road_names.contains(road.as_str()) {
+                errors.push(WorldError::UnknownEventRoad {
+                    event: event.key.clone(),
+                    road: road.clone(),
+                });
+            }
+        }
+        for region in &event.conditions.regions {
+            if !regions.contains(region.as_str()) {
+                errors.push(WorldError::UnknownEventRegion {
+                    event: event.key.clone(),
+                    region: region.clone(),
+                });
+            }
+        }
+    }
+
+    if world.starting_position.is_empty() {
+        errors.push(WorldError::MissingStartingPosition);
+    } else if let Some(start) = city_nodes.get(world.starting_position.as_str()) {
+        let components = cities.connected_components();
+        let reachable = components.iter().find(|c| c.contains(start)).unwrap();
+        for (node, name) in cities.nodes() {
+            if !reachable.contains(&node) {
+                errors.push(WorldError::UnreachableCity(name.to_string()));
+            }
+        }
+    } else {
+        errors.push(WorldError::UnknownStartingPosition(
+            world.starting_position.clone(),
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// The road graph and item database built from a validated `WorldData`.
+pub struct World {
+    pub map: Graph<City, RoadData>,
+    pub items: ItemDatabase,
+    pub recipes: RecipeBook,
+    pub events: EventBook,
+    pub starting_position: NodeIndex,
+    pub starting_gold: u32,
+    pub carry_capacity: Option<u32>,
+    /// Miles between every pair of cities, worked out once at load.
+    pub distances: DistanceMatrix,
+    /// Roads that are the only link between two parts of the map.
+    pub chokepoints: HashSet<EdgeIndex>,
+    cities: HashMap<String, NodeIndex>,
+}
+
+impl World {
+    /// Parses, validates and builds a world from the contents of a world file.
+    pub fn load(serialized_world: &str) -> Result<World, Vec<WorldError>> {
+        check_world(serialized_world)?.try_into()
+    }
+
+    /// A player at the starting position with the configured gold and carry capacity.
+    pub fn new_player(&self) -> PlayerState {
+        let mut player = PlayerState::new(self.starting_position, self.starting_gold);
+        player.inventory.capacity = self.carry_capacity;
+        player
+    }
+
+    pub fn city_index(&self, name: &str) -> Option<NodeIndex> {
+        self.cities.get(name).copied()
+    }
+
+    pub fn city(&self, name: &str) -> Option<&City> {
+        self.map.get_node(self.city_index(name)?)
+    }
+
+    /**
+        The reachable city other than `here` that pays the most for `item`, with
+        its price and distance in miles. Ties go to the nearer city.
+    */
+    pub fn best_market_for(
+        &self,
+        item: ItemIndex,
+        here: NodeIndex,
+    ) -> Option<(NodeIndex, u32, u64)> {
+        self.map
+            .nodes()
+            .filter(|(node, _)| *node != here)
+            .filter_map(|(node, city)| {
+                let miles = self.distances.distance(here, node)?;
+                Some((node, market::sell_price(city, &self.items, item)?, miles))
+            })
+            .min_by_key(|(node, price, miles)| (std::cmp::Reverse(*price), *miles, node.0))
+    }
+}
+
+impl TryFrom<WorldData> for World {
+    type Error = Vec<WorldError>;
+
+    fn try_from(world: WorldData) -> Result<Self, Self::Error> {
+        validate_world(&world)?;
+
+        let mut map: Graph<City, RoadData> = Graph::new();
+        let mut cities: HashMap<String, NodeIndex> = HashMap::new();
+        for city in &world.cities {
+            let idx = map.insert_node(city.into());
+            cities.insert(city.name.clone(), idx);
+        }
+
+        for city in &world.cities {
+            for road in &city.roads {
+                map.insert_edge_undirected(road.clone(), cities[&city.name], cities[&road.to]);
+            }
+        }
+
+        let mut items = ItemDatabase::new();
+        for item in world.items {
+            items.insert(item);
+        }
+
+        let mut recipes = RecipeBook::new();
+        for recipe in &world.recipes {
+            let resolve = |ingredients: &[IngredientData]| {
+                ingredients
+                    .iter()
+                    .map(|i| (*items.get_index(&i.item).unwrap(), i.quantity))
+                    .collect()
+            };
+            recipes.insert(Recipe {
+                key: recipe.key.clone(),
+                name: recipe.name.clone(),
+                inputs: resolve(&recipe.inputs),
+                outputs: resolve(&recipe.outputs),
+                days: recipe.days.max(1),
+                city: recipe.city.clone(),
+                workshop: recipe.workshop.clone(),
+            });
+        }
+
+        for city in &world.cities {
+            let city_node = map.get_node_mut(cities[&city.name]).unwrap();
+            for recipe in &city.recipes {
+                let recipe = *recipes.get_index(recipe).unwrap();
+                city_node.production.push(Production::new(recipe));
+            }
+            for listing in &city.market {
+                let item = *items.get_index(&listing.item).unwrap();
+                city_node.market.add_listing(item, listing.economy());
+                city_node.inventory.add_item(item, listing.stock);
+            }
+        }
+
+        let mut events = EventBook::new();
+        for event in &world.events {
+            events.insert(Event::resolve(event, &items).unwrap());
+        }
+
+        Ok(World {
+            items,
+            recipes,
+            events,
+            starting_position: cities[&world.starting_position],
+            starting_gold: world.starting_gold,
+            carry_capacity: world.carry_capacity,
+            distances: map.distance_matrix(),
+            chokepoints: map.bridges().into_iter().collect(),
+            map,
+            cities,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BROKEN_WORLD: &str = r#"
+starting_position = "Alpha"
+
+[[items]]
+key = "fish"
+name = "Fish"
+value = 10
+tags = ["Edible"]
+
+[[items]]
+key = "fish"
+name = "Other Fish"
+value = 12
+
+[[items]]
+key = "grapes"
+name = "Grapes"
+value = 4
+shelf_life = 5
+spoils_into = "raisins"
+
+[[items]]
+key = "raisins"
+name = "Raisins"
+value = 6
+shelf_life = 30
+spoils_into = "grapes"
+
+[[cities]]
+name = "Alpha"
+description = ""
+[[cities.roads]]
+to = "Beta"
+name = "Short Road"
+distance = 0
+[[cities.roads]]
+to = "Nowhere"
+name = "Lost Road"
+distance = 10
+[[cities.market]]
+item = "salt"
+
+[[cities]]
+name = "Beta"
+description = ""
+roads = []
+
+[[cities]]
+name = "Gamma"
+description = ""
+roads = []
+
+[[cities]]
+name = "Beta"
+description = ""
+roads = []
+"#;
+
+    #[test]
+    fn reports_every_problem() {
+        let errors = check_world(BROKEN_WORLD).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                WorldError::DuplicateCity("Beta".into()),
+                WorldError::EdibleWithoutNutrition("fish".into()),
+                WorldError::DuplicateItem("fish".into()),
+                WorldError::SpoilageCycle(vec!["grapes".into(), "raisins".into(), "grapes".into()]),
+                WorldError::ZeroDistanceRoad {
+                    city: "Alpha".into(),
+                    road: "Short Road".into()
+                },
+                WorldError::UnknownRoadTarget {
+                    city: "Alpha".into(),
+                    road: "Lost Road".into(),
+                    to: "Nowhere".into()
+                },
+                WorldError::UnknownMarketItem {
+                    city: "Alpha".into(),
+                    item: "salt".into()
+                },
+                WorldError::UnreachableCity("Gamma".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors_carry_line_and_column() {
+        let errors = check_world("starting_position = \"Alpha\"\ncities = 3\n").unwrap_err();
+        match &errors[..] {
+            [WorldError::Parse {
+                line: Some(2),
+                column: Some(10),
+                ..
+            }] => {}
+            other => panic!("unexpected errors {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bundled_world_is_valid() {
+        check_world(include_str!("../../data/world.toml")).unwrap();
+    }
+}
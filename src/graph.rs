@@ -1,4 +1,13 @@
-use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::marker::PhantomData;
+use std::ops::Add;
+
+pub mod connectivity;
+pub mod csr;
+
+pub use connectivity::Connectivity;
+pub use csr::CsrGraph;
 
 type GraphIndex = usize;
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Copy)]
@@ -7,185 +16,285 @@ pub struct NodeIndex(pub GraphIndex);
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Copy)]
 pub struct EdgeIndex(pub GraphIndex);
 pub type EdgePair = (EdgeIndex, NodeIndex);
-struct FrontierEntry<T>(u32, T);
-impl<T> PartialEq for FrontierEntry<T> {
+
+/**
+    What it takes to traverse an edge. Costs only have to be ordered and add up,
+    so fractional hours or a struct combining several measures work as well as
+    whole miles. `Default` must be zero.
+
+    Costs are summed along a path as `Total`, which can be wider than the cost
+    itself so long routes do not overflow. A path whose total cannot be compared,
+    such as a NaN, is treated as impassable.
+*/
+pub trait Cost: Copy + PartialOrd + Default {
+    type Total: Copy + PartialOrd + Default + Add<Output = Self::Total>;
+
+    fn total(self) -> Self::Total;
+}
+
+macro_rules! impl_cost {
+    ($($cost:ty => $total:ty),*) => {
+        $(impl Cost for $cost {
+            type Total = $total;
+
+            fn total(self) -> $total {
+                self.into()
+            }
+        })*
+    };
+}
+
+impl_cost!(u8 => u64, u16 => u64, u32 => u64, u64 => u64, f32 => f64, f64 => f64);
+
+/// Total cost of a path through a graph searched with `Search`.
+pub type TotalCost<S> = <<S as Search>::Cost as Cost>::Total;
+
+/// Min-heap entry. Incomparable costs, such as NaN, are treated as equal.
+struct FrontierEntry<C, T>(C, T);
+impl<C: PartialOrd, T> PartialEq for FrontierEntry<C, T> {
     fn eq(&self, other: &Self) -> bool {
-        other.0.eq(&self.0)
+        self.cmp(other) == Ordering::Equal
     }
 }
 
-impl<T> Eq for FrontierEntry<T> {}
+impl<C: PartialOrd, T> Eq for FrontierEntry<C, T> {}
 
-impl<T> PartialOrd for FrontierEntry<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        other.0.partial_cmp(&self.0)
+impl<C: PartialOrd, T> PartialOrd for FrontierEntry<C, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl<T> Ord for FrontierEntry<T> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.0.cmp(&self.0)
+impl<C: PartialOrd, T> Ord for FrontierEntry<C, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.partial_cmp(&self.0).unwrap_or(Ordering::Equal)
     }
 }
 
-pub trait Edge {
-    fn cost(&self) -> u32 {
-        0
+/// A route found by one of the searches.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Path<C = u64> {
+    /// Every node visited, starting with the origin and ending with the target.
+    pub nodes: Vec<NodeIndex>,
+    /// The edge taken between each pair of nodes, so one shorter than `nodes`.
+    pub edges: Vec<EdgeIndex>,
+    /// Sum of the cost of every edge taken.
+    pub cost: C,
+}
+
+impl<C> Path<C> {
+    /// Each edge paired with the node it leads to.
+    pub fn legs(&self) -> impl Iterator<Item = EdgePair> + '_ {
+        self.edges
+            .iter()
+            .copied()
+            .zip(self.nodes[1..].iter().copied())
+    }
+}
+
+/// Result of `Graph::shortest_path_tree`.
+#[derive(Clone, Debug)]
+pub struct ShortestPathTree<C = u64> {
+    from: NodeIndex,
+    /// Indexed by node, `None` where the node cannot be reached.
+    distances: Vec<Option<C>>,
+    /// The edge and node each reachable node is entered from, indexed by node.
+    predecessors: Vec<Option<EdgePair>>,
+}
+
+impl<C: Copy> ShortestPathTree<C> {
+    pub fn from(&self) -> NodeIndex {
+        self.from
+    }
+
+    /// Cost of the cheapest path to `to`, or `None` if it cannot be reached.
+    pub fn distance(&self, to: NodeIndex) -> Option<C> {
+        *self.distances.get(to.0)?
+    }
+
+    pub fn predecessor(&self, to: NodeIndex) -> Option<EdgePair> {
+        *self.predecessors.get(to.0)?
+    }
+
+    /// Every reachable node with its distance in index order, the origin included.
+    pub fn distances(&self) -> impl Iterator<Item = (NodeIndex, C)> + '_ {
+        self.distances
+            .iter()
+            .enumerate()
+            .filter_map(|(index, cost)| Some((NodeIndex(index), (*cost)?)))
+    }
+
+    pub fn path_to(&self, to: NodeIndex) -> Option<Path<C>> {
+        let cost = self.distance(to)?;
+        let mut nodes = vec![to];
+        let mut edges = Vec::new();
+        let mut current = to;
+        while let Some((edge, previous)) = self.predecessor(current) {
+            edges.push(edge);
+            nodes.push(previous);
+            current = previous;
+        }
+        nodes.reverse();
+        edges.reverse();
+        Some(Path { nodes, edges, cost })
+    }
+}
+
+/// Cheapest distance between every pair of nodes, from `Graph::distance_matrix`.
+#[derive(Clone, Debug)]
+pub struct DistanceMatrix<C = u64> {
+    distances: Vec<Vec<Option<C>>>,
+}
+
+impl<C: Copy> DistanceMatrix<C> {
+    pub fn distance(&self, from: NodeIndex, to: NodeIndex) -> Option<C> {
+        *self.distances.get(from.0)?.get(to.0)?
+    }
+}
+
+pub trait Edge<C: Cost = u32> {
+    fn cost(&self) -> C {
+        C::default()
     }
 }
 
 #[derive(Debug)]
-pub struct Graph<T, E: Edge> {
-    nodes: Vec<T>,
-    edges: Vec<E>,
-    connections: HashMap<NodeIndex, HashSet<EdgePair>>,
+pub struct Graph<T, E: Edge<C>, C: Cost = u32> {
+    /// Removed nodes and edges leave a `None` behind so indices stay stable.
+    nodes: Vec<Option<T>>,
+    edges: Vec<Option<E>>,
+    /// Edges leaving each node, by node index, in the order they were inserted.
+    connections: Vec<Vec<EdgePair>>,
+    cost: PhantomData<C>,
 }
 
-impl<T, E: Edge> Graph<T, E> {
-    pub fn new() -> Graph<T, E> {
+impl<T, E: Edge<C>, C: Cost> Default for Graph<T, E, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, E: Edge<C>, C: Cost> Graph<T, E, C> {
+    pub fn new() -> Graph<T, E, C> {
         Graph {
             nodes: Vec::new(),
             edges: Vec::new(),
-            connections: HashMap::new(),
+            connections: Vec::new(),
+            cost: PhantomData,
         }
     }
 
     pub fn insert_node(&mut self, node: T) -> NodeIndex {
         let index = self.nodes.len();
-        self.nodes.push(node);
+        self.nodes.push(Some(node));
+        self.connections.push(Vec::new());
         NodeIndex(index)
     }
 
     pub fn get_node(&self, node: NodeIndex) -> Option<&T> {
-        self.nodes.get(node.0)
+        self.nodes.get(node.0)?.as_ref()
+    }
+
+    pub fn get_node_mut(&mut self, node: NodeIndex) -> Option<&mut T> {
+        self.nodes.get_mut(node.0)?.as_mut()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeIndex, &T)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, node)| Some((NodeIndex(index), node.as_ref()?)))
+    }
+
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.nodes.iter_mut().flatten()
     }
 
     /**
         Inserts a directed edge.
+
+        Panics if either end is not a node in the graph, including removed nodes.
     */
     pub fn insert_edge(&mut self, edge: E, conn_a: NodeIndex, conn_b: NodeIndex) -> EdgeIndex {
+        self.assert_endpoints(conn_a, conn_b);
         let index = self.edges.len();
-        self.edges.push(edge);
-        let set = self.connections.entry(conn_a).or_insert(HashSet::new());
-        set.insert((EdgeIndex(index), conn_b));
+        self.edges.push(Some(edge));
+        self.connections[conn_a.0].push((EdgeIndex(index), conn_b));
         EdgeIndex(index)
     }
 
+    /// Inserts an edge that can be taken both ways. Panics like `insert_edge`.
     pub fn insert_edge_undirected(
         &mut self,
         edge: E,
         conn_a: NodeIndex,
         conn_b: NodeIndex,
     ) -> EdgeIndex {
+        self.assert_endpoints(conn_a, conn_b);
         let index = self.edges.len();
-        self.edges.push(edge);
-        let set1 = self.connections.entry(conn_a).or_insert(HashSet::new());
-        set1.insert((EdgeIndex(index), conn_b));
-        let set2 = self.connections.entry(conn_b).or_insert(HashSet::new());
-        set2.insert((EdgeIndex(index), conn_a));
+        self.edges.push(Some(edge));
+        self.connections[conn_a.0].push((EdgeIndex(index), conn_b));
+        // A loop is only listed once, as with a directed edge.
+        if conn_a != conn_b {
+            self.connections[conn_b.0].push((EdgeIndex(index), conn_a));
+        }
         EdgeIndex(index)
     }
 
-    pub fn get_edge(&self, edge: EdgeIndex) -> Option<&E> {
-        self.edges.get(edge.0)
+    fn assert_endpoints(&self, conn_a: NodeIndex, conn_b: NodeIndex) {
+        for node in [conn_a, conn_b] {
+            assert!(
+                self.get_node(node).is_some(),
+                "cannot connect {:?}: it is not a node in the graph",
+                node
+            );
+        }
     }
 
-    pub fn get_connections(&self, node: NodeIndex) -> Option<&HashSet<EdgePair>> {
-        self.connections.get(&node)
+    pub fn get_edge(&self, edge: EdgeIndex) -> Option<&E> {
+        self.edges.get(edge.0)?.as_ref()
     }
 
-    pub fn bfs(&self, from: NodeIndex, to: NodeIndex) -> Option<Vec<NodeIndex>> {
-        let mut explored = HashMap::<NodeIndex, NodeIndex>::new(); // node, from
-        let mut frontier = VecDeque::<NodeIndex>::new();
-        explored.insert(from, from);
-        frontier.push_back(from);
-
-        while let Some(current) = frontier.pop_front() {
-            if let Some(connections) = self.get_connections(current) {
-                for (_, node) in connections {
-                    if explored.contains_key(node) {
-                        continue;
-                    }
-                    explored.insert(*node, current);
-                    if *node == to {
-                        let mut out: Vec<NodeIndex> = Vec::new();
-                        let mut current = to;
-                        while current != from {
-                            out.push(current);
-                            let from_node = explored.get(&current).unwrap();
-                            current = *from_node
-                        }
-                        out.push(from);
-                        out.reverse();
-                        return Some(out);
-                    } else {
-                        frontier.push_back(*node);
-                    }
-                }
-            }
+    /**
+        Removes an edge, returning it. Its index is never reused, so `get_edge`
+        returns `None` for it from now on.
+    */
+    pub fn remove_edge(&mut self, edge: EdgeIndex) -> Option<E> {
+        let removed = self.edges.get_mut(edge.0)?.take()?;
+        for connections in self.connections.iter_mut() {
+            connections.retain(|(e, _)| *e != edge);
         }
-        None
+        Some(removed)
     }
 
-    pub fn astar(
-        &self,
-        from: NodeIndex,
-        to: NodeIndex,
-        h: impl Fn(&T, &T) -> u32,
-    ) -> Option<Vec<NodeIndex>> {
-        let mut frontier: BinaryHeap<FrontierEntry<NodeIndex>> = BinaryHeap::new();
-        let mut explored = HashMap::<NodeIndex, (u64, NodeIndex)>::new(); // cost, from
-        explored.insert(from, (0, from));
-        frontier.push(FrontierEntry(0, from));
-
-        while let Some(current) = frontier.pop() {
-            let current_cost;
-            {
-                let (cc, _) = explored.get(&current.1).unwrap();
-                current_cost = *cc;
-            }
-            if let Some(connections) = self.get_connections(current.1) {
-                for (edge_index, node) in connections {
-                    let edge = self.get_edge(*edge_index).unwrap();
-                    let new_cost = current_cost + edge.cost() as u64;
-                    {
-                        let mut_node = explored.get_mut(node);
-                        // Update the node if new route is faster than the old route
-                        if let Some(entry) = mut_node {
-                            if entry.0 > new_cost {
-                                *entry = (new_cost, *node);
-                            }
-                            continue;
-                        }
-                    }
-                    explored.insert(*node, (current_cost, current.1));
-                    if *node == to {
-                        let mut out: Vec<NodeIndex> = Vec::new();
-                        let mut current = to;
-                        while current != from {
-                            out.push(current);
-                            let (_, from_node) = explored.get(&current).unwrap();
-                            current = *from_node
-                        }
-                        out.push(from);
-                        out.reverse();
-                        return Some(out);
-                    } else {
-                        frontier.push(FrontierEntry(
-                            edge.cost()
-                                + h(self.get_node(*node).unwrap(), self.get_node(to).unwrap()),
-                            *node,
-                        ));
-                    }
+    /**
+        Removes a node along with every edge leading to or from it, returning the
+        node. Its index is never reused, so `get_node` returns `None` for it from now on.
+    */
+    pub fn remove_node(&mut self, node: NodeIndex) -> Option<T> {
+        let removed = self.nodes.get_mut(node.0)?.take()?;
+        let mut edges: HashSet<EdgeIndex> = std::mem::take(&mut self.connections[node.0])
+            .into_iter()
+            .map(|(edge, _)| edge)
+            .collect();
+        for connections in self.connections.iter_mut() {
+            connections.retain(|(edge, to)| {
+                if *to == node {
+                    edges.insert(*edge);
                 }
-            }
+                *to != node
+            });
         }
-        None
+        for edge in edges {
+            self.edges[edge.0] = None;
+        }
+        Some(removed)
     }
 
-    pub fn dijkstra(&self, from: NodeIndex, to: NodeIndex) -> Option<Vec<NodeIndex>> {
-        // I tested the extra overhead of even calling the extra function h. It seems rust basically removes the cost all-together
-        self.astar(from, to, |_, _| 0)
+    /// Edges leaving `node` in insertion order, or `None` if there is no such node.
+    pub fn get_connections(&self, node: NodeIndex) -> Option<&[EdgePair]> {
+        self.get_node(node)?;
+        Some(&self.connections[node.0])
     }
 
     pub fn to_mermaid_format(
@@ -194,9 +303,9 @@ impl<T, E: Edge> Graph<T, E> {
         format_edge: impl Fn(&E) -> String,
     ) -> String {
         let mut out = "graph TD;".to_string();
-        for (from_node_index, edges) in self.connections.iter() {
-            let from_node_label =
-                format_node(self.get_node(*from_node_index).unwrap(), *from_node_index);
+        for (from_node_index, from_node) in self.nodes() {
+            let from_node_label = format_node(from_node, from_node_index);
+            let edges = &self.connections[from_node_index.0];
             for (edge_index, to_node_index) in edges.iter() {
                 let edge = self.get_edge(*edge_index).unwrap();
                 let to_node_label =
@@ -212,7 +321,10 @@ impl<T, E: Edge> Graph<T, E> {
         out
     }
 
-    pub fn to_mermaid(&self) -> String {
+    pub fn to_mermaid(&self) -> String
+    where
+        C: std::fmt::Display,
+    {
         self.to_mermaid_format(
             |_, index| index.0.to_string(),
             |edge| edge.cost().to_string(),
@@ -220,10 +332,264 @@ impl<T, E: Edge> Graph<T, E> {
     }
 }
 
+impl<T, E: Edge<C>, C: Cost> Search for Graph<T, E, C> {
+    type Node = T;
+    type Edge = E;
+    type Cost = C;
+
+    fn node(&self, node: NodeIndex) -> Option<&T> {
+        self.get_node(node)
+    }
+
+    fn edge(&self, edge: EdgeIndex) -> Option<&E> {
+        self.get_edge(edge)
+    }
+
+    fn neighbors(&self, node: NodeIndex) -> impl Iterator<Item = EdgePair> + '_ {
+        self.get_connections(node).into_iter().flatten().copied()
+    }
+
+    fn node_bound(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/**
+    Path finding shared by every graph representation. Implementors only say how
+    to look up nodes, edges and neighbors.
+*/
+pub trait Search {
+    type Node;
+    type Edge: Edge<Self::Cost>;
+    type Cost: Cost;
+
+    fn node(&self, node: NodeIndex) -> Option<&Self::Node>;
+
+    fn edge(&self, edge: EdgeIndex) -> Option<&Self::Edge>;
+
+    /// The edges leaving `node`, each with the node it leads to.
+    fn neighbors(&self, node: NodeIndex) -> impl Iterator<Item = EdgePair> + '_;
+
+    /// One more than the highest node index ever handed out.
+    fn node_bound(&self) -> usize;
+
+    /**
+        Walks back from `to` to `from` using `came_from`, which gives the node and
+        edge each node was reached by.
+    */
+    fn trace_path(
+        &self,
+        from: NodeIndex,
+        to: NodeIndex,
+        came_from: impl Fn(NodeIndex) -> EdgePair,
+    ) -> Path<TotalCost<Self>> {
+        self.trace_path_by(from, to, came_from, |edge, _, _| edge.cost())
+    }
+
+    /// Like `trace_path`, but adds up the path's cost with `cost` instead of `Edge::cost`.
+    fn trace_path_by<C: Cost>(
+        &self,
+        from: NodeIndex,
+        to: NodeIndex,
+        came_from: impl Fn(NodeIndex) -> EdgePair,
+        cost: impl Fn(&Self::Edge, NodeIndex, NodeIndex) -> C,
+    ) -> Path<C::Total> {
+        let mut nodes = vec![to];
+        let mut edges = Vec::new();
+        let mut total = C::Total::default();
+        let mut current = to;
+        while current != from {
+            let (edge, previous) = came_from(current);
+            total = total + cost(self.edge(edge).unwrap(), previous, current).total();
+            edges.push(edge);
+            nodes.push(previous);
+            current = previous;
+        }
+        nodes.reverse();
+        edges.reverse();
+        Path {
+            nodes,
+            edges,
+            cost: total,
+        }
+    }
+
+    fn bfs(&self, from: NodeIndex, to: NodeIndex) -> Option<Path<TotalCost<Self>>> {
+        if from == to {
+            return self
+                .node(from)
+                .map(|_| self.trace_path(from, to, |_| unreachable!()));
+        }
+        let mut explored: Vec<Option<EdgePair>> = vec![None; self.node_bound()]; // (edge, from)
+        let mut frontier = VecDeque::<NodeIndex>::new();
+        frontier.push_back(from);
+
+        while let Some(current) = frontier.pop_front() {
+            for (edge_index, node) in self.neighbors(current) {
+                if node == from || explored[node.0].is_some() {
+                    continue;
+                }
+                explored[node.0] = Some((edge_index, current));
+                if node == to {
+                    return Some(self.trace_path(from, to, |node| explored[node.0].unwrap()));
+                } else {
+                    frontier.push_back(node);
+                }
+            }
+        }
+        None
+    }
+
+    /**
+        Expands nodes cheapest first from `from`, adding `h` of each node to its
+        priority, until `target` is popped or everything reachable is settled.
+        Returns, by node index, the cheapest known cost and the (edge, node) each
+        node was reached by.
+    */
+    fn explore(
+        &self,
+        from: NodeIndex,
+        target: Option<NodeIndex>,
+        h: impl Fn(&Self::Node) -> TotalCost<Self>,
+    ) -> Vec<Option<(TotalCost<Self>, EdgePair)>> {
+        self.explore_by(from, target, h, |edge, _, _| edge.cost())
+    }
+
+    /**
+        Like `explore`, but each edge costs whatever `cost` returns for it, given
+        the node it leaves and the node it leads to.
+    */
+    fn explore_by<C: Cost>(
+        &self,
+        from: NodeIndex,
+        target: Option<NodeIndex>,
+        h: impl Fn(&Self::Node) -> C::Total,
+        cost: impl Fn(&Self::Edge, NodeIndex, NodeIndex) -> C,
+    ) -> Vec<Option<(C::Total, EdgePair)>> {
+        let zero = C::Total::default();
+        // Entries are keyed by cost so far plus the estimate, and carry the cost so far.
+        let mut frontier = BinaryHeap::new();
+        // cheapest known cost, (edge, from). The origin's edge is never read.
+        let mut explored: Vec<Option<(C::Total, EdgePair)>> = vec![None; self.node_bound()];
+        let Some(origin) = self.node(from) else {
+            return explored;
+        };
+        explored[from.0] = Some((zero, (EdgeIndex(usize::MAX), from)));
+        frontier.push(FrontierEntry(h(origin), (zero, from)));
+
+        while let Some(FrontierEntry(_, (current_cost, current))) = frontier.pop() {
+            // Only once a node is popped is its cost final.
+            if Some(current) == target {
+                break;
+            }
+            // A cheaper route to this node was queued after this entry.
+            if explored[current.0].is_some_and(|(cost, _)| current_cost > cost) {
+                continue;
+            }
+            for (edge_index, node) in self.neighbors(current) {
+                let edge = self.edge(edge_index).unwrap();
+                let new_cost = current_cost + cost(edge, current, node).total();
+                // A NaN never compares as cheaper or dearer, so it could be queued forever.
+                if new_cost.partial_cmp(&new_cost).is_none() {
+                    continue;
+                }
+                if explored[node.0].is_some_and(|(cost, _)| cost <= new_cost) {
+                    continue;
+                }
+                explored[node.0] = Some((new_cost, (edge_index, current)));
+                let estimate = h(self.node(node).unwrap());
+                frontier.push(FrontierEntry(new_cost + estimate, (new_cost, node)));
+            }
+        }
+        explored
+    }
+
+    /**
+        Finds the cheapest path from `from` to `to`. `h` estimates the remaining
+        cost between two nodes and must never overestimate it, or the path found
+        may not be the cheapest.
+    */
+    fn astar(
+        &self,
+        from: NodeIndex,
+        to: NodeIndex,
+        h: impl Fn(&Self::Node, &Self::Node) -> Self::Cost,
+    ) -> Option<Path<TotalCost<Self>>> {
+        self.astar_by(from, to, h, |edge, _, _| edge.cost())
+    }
+
+    /**
+        Like `astar`, but each edge costs whatever `cost` returns for it, given the
+        node it leaves and the node it leads to. This lets one graph be searched by
+        different measures, such as distance or danger.
+    */
+    fn astar_by<C: Cost>(
+        &self,
+        from: NodeIndex,
+        to: NodeIndex,
+        h: impl Fn(&Self::Node, &Self::Node) -> C,
+        cost: impl Fn(&Self::Edge, NodeIndex, NodeIndex) -> C,
+    ) -> Option<Path<C::Total>> {
+        let target = self.node(to)?;
+        let explored = self.explore_by(from, Some(to), |node| h(node, target).total(), &cost);
+        explored[to.0]
+            .map(|_| self.trace_path_by(from, to, |node| explored[node.0].unwrap().1, &cost))
+    }
+
+    fn dijkstra(&self, from: NodeIndex, to: NodeIndex) -> Option<Path<TotalCost<Self>>> {
+        // I tested the extra overhead of even calling the extra function h. It seems rust basically removes the cost all-together
+        self.astar(from, to, |_, _| Self::Cost::default())
+    }
+
+    /// Cheapest path from `from` to `to`, with each edge costing what `cost` returns.
+    fn dijkstra_by<C: Cost>(
+        &self,
+        from: NodeIndex,
+        to: NodeIndex,
+        cost: impl Fn(&Self::Edge, NodeIndex, NodeIndex) -> C,
+    ) -> Option<Path<C::Total>> {
+        self.astar_by(from, to, |_, _| C::default(), cost)
+    }
+
+    /// Cheapest distances and routes from `from` to every node it can reach.
+    fn shortest_path_tree(&self, from: NodeIndex) -> ShortestPathTree<TotalCost<Self>> {
+        let explored = self.explore(from, None, |_| TotalCost::<Self>::default());
+        let mut tree = ShortestPathTree {
+            from,
+            distances: vec![None; explored.len()],
+            predecessors: vec![None; explored.len()],
+        };
+        for (index, entry) in explored.into_iter().enumerate() {
+            let Some((cost, came_from)) = entry else {
+                continue;
+            };
+            tree.distances[index] = Some(cost);
+            if index != from.0 {
+                tree.predecessors[index] = Some(came_from);
+            }
+        }
+        tree
+    }
+
+    /// Cheapest distance between every pair of nodes, from one tree per node.
+    fn distance_matrix(&self) -> DistanceMatrix<TotalCost<Self>> {
+        let distances = (0..self.node_bound())
+            .map(|from| {
+                let tree = self.shortest_path_tree(NodeIndex(from));
+                (0..self.node_bound())
+                    .map(|to| tree.distance(NodeIndex(to)))
+                    .collect()
+            })
+            .collect();
+        DistanceMatrix { distances }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate test;
     use super::*;
+    use std::collections::HashMap;
     use test::Bencher;
 
     #[derive(Debug)]
@@ -268,14 +634,12 @@ mod tests {
         });
     }
 
-    #[bench]
-    fn bench_huge_dijkstra(bench: &mut Bencher) {
+    fn facebook_graph() -> (Graph<u32, EmptyEdge>, HashMap<u32, NodeIndex>) {
         let data = include_str!("../data/test/facebook_combined.txt")
             .split("\n")
             .map(|s| {
                 s.split_whitespace()
-                    .map(|s| s.parse::<u32>().ok())
-                    .filter_map(|x| x)
+                    .filter_map(|s| s.parse::<u32>().ok())
                     .collect()
             })
             .collect::<Vec<Vec<u32>>>();
@@ -289,20 +653,20 @@ mod tests {
             }
             let first = vec[0];
             let second = vec[1];
-            if !nodes.contains_key(&first) {
-                let idx = g.insert_node(first);
-                nodes.insert(first, idx);
-            }
-            if !nodes.contains_key(&second) {
-                let idx = g.insert_node(second);
-                nodes.insert(second, idx);
-            }
+            nodes.entry(first).or_insert_with(|| g.insert_node(first));
+            nodes.entry(second).or_insert_with(|| g.insert_node(second));
             g.insert_edge(
                 EmptyEdge,
                 *nodes.get(&first).unwrap(),
                 *nodes.get(&second).unwrap(),
             );
         }
+        (g, nodes)
+    }
+
+    #[bench]
+    fn bench_huge_dijkstra(bench: &mut Bencher) {
+        let (g, nodes) = facebook_graph();
 
         bench.iter(|| {
             g.bfs(*nodes.get(&0).unwrap(), *nodes.get(&3080).unwrap());
@@ -312,12 +676,23 @@ mod tests {
             "{:?}",
             g.bfs(*nodes.get(&0).unwrap(), *nodes.get(&3080).unwrap())
                 .unwrap()
+                .nodes
                 .iter()
                 .map(|a| *g.get_node(*a).unwrap())
                 .collect::<Vec<u32>>()
         );
     }
 
+    #[bench]
+    fn bench_huge_dijkstra_csr(bench: &mut Bencher) {
+        let (g, nodes) = facebook_graph();
+        let g = CsrGraph::from(g);
+
+        bench.iter(|| {
+            g.bfs(*nodes.get(&0).unwrap(), *nodes.get(&3080).unwrap());
+        });
+    }
+
     #[bench]
     fn bfs_performs_well(bench: &mut Bencher) {
         let mut g: Graph<String, WeightedEdge> = Graph::new();
@@ -364,11 +739,13 @@ mod tests {
 
         let path_bfs: Vec<String> = result_bfs
             .unwrap()
+            .nodes
             .iter()
             .map(|n| g.get_node(*n).unwrap().clone())
             .collect();
         let path_dijkstra: Vec<String> = result_dijkstra
             .unwrap()
+            .nodes
             .iter()
             .map(|n| g.get_node(*n).unwrap().clone())
             .collect();
@@ -383,6 +760,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn paths_carry_edges_and_cost() {
+        let mut g: Graph<String, WeightedEdge> = Graph::new();
+
+        let a = g.insert_node("A".into());
+        let b = g.insert_node("B".into());
+        let c = g.insert_node("C".into());
+
+        let ab = g.insert_edge(WeightedEdge(1), a, b);
+        let bc = g.insert_edge(WeightedEdge(2), b, c);
+
+        let path = g.bfs(a, c).expect("bfs did not resolve");
+        assert_eq!(path.nodes, [a, b, c]);
+        assert_eq!(path.edges, [ab, bc]);
+        assert_eq!(path.cost, 3);
+        assert_eq!(path.legs().collect::<Vec<_>>(), [(ab, b), (bc, c)]);
+    }
+
     #[test]
     fn dijkstra_with_cycles() {
         let mut g: Graph<String, EmptyEdge> = Graph::new();
@@ -406,6 +801,7 @@ mod tests {
 
         let path_strings: Vec<String> = result_dijkstra
             .expect("dijkstra did not resolve")
+            .nodes
             .iter()
             .map(|n| g.get_node(*n).unwrap().clone())
             .collect();
@@ -433,13 +829,15 @@ mod tests {
 
         let path_strings: Vec<String> = result_dijkstra
             .expect("dijkstra did not resolve")
+            .nodes
             .iter()
             .map(|n| g.get_node(*n).unwrap().clone())
             .collect();
 
+        // Going through B costs 3 against 4 for the direct edge.
         assert_eq!(
             path_strings.join("->"),
-            "A->C",
+            "A->B->C",
             "Dijkstra path is incorrect"
         );
     }
@@ -468,6 +866,7 @@ mod tests {
 
         let path_strings: Vec<String> = result_dijkstra
             .expect("dijkstra did not resolve")
+            .nodes
             .iter()
             .map(|n| g.get_node(*n).unwrap().clone())
             .collect();
@@ -480,6 +879,525 @@ mod tests {
         );
     }
 
+    /// Cheapest cost from `from` to every node, by relaxing every edge until nothing changes.
+    fn brute_force_costs(g: &Graph<u32, WeightedEdge>, from: NodeIndex) -> Vec<Option<u64>> {
+        let mut costs = vec![None; g.nodes.len()];
+        costs[from.0] = Some(0);
+        loop {
+            let mut changed = false;
+            for (node, connections) in g.connections.iter().enumerate() {
+                let Some(cost) = costs[node] else {
+                    continue;
+                };
+                for (edge, to) in connections {
+                    let new_cost = cost + g.get_edge(*edge).unwrap().cost() as u64;
+                    if costs[to.0].is_none_or(|old| new_cost < old) {
+                        costs[to.0] = Some(new_cost);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return costs;
+            }
+        }
+    }
+
+    /**
+        Random graph whose nodes are positions on a line. No edge costs less than the
+        distance between its ends, so that distance is an admissible A* heuristic.
+    */
+    fn random_graph(rng: &mut fastrand::Rng) -> Graph<u32, WeightedEdge> {
+        let mut g = Graph::new();
+        let nodes: Vec<NodeIndex> = (0..rng.usize(1..12))
+            .map(|_| g.insert_node(rng.u32(0..50)))
+            .collect();
+        for _ in 0..rng.usize(0..30) {
+            let a = nodes[rng.usize(..nodes.len())];
+            let b = nodes[rng.usize(..nodes.len())];
+            let distance = g.get_node(a).unwrap().abs_diff(*g.get_node(b).unwrap());
+            let edge = WeightedEdge(distance + rng.u32(0..20));
+            if rng.bool() {
+                g.insert_edge(edge, a, b);
+            } else {
+                g.insert_edge_undirected(edge, a, b);
+            }
+        }
+        // Leave some tombstones behind for the searches to skip.
+        for _ in 0..rng.usize(0..3) {
+            g.remove_edge(EdgeIndex(rng.usize(..g.edges.len().max(1))));
+        }
+        if rng.u8(..4) == 0 {
+            g.remove_node(nodes[rng.usize(..nodes.len())]);
+        }
+        g
+    }
+
+    fn assert_valid_path(
+        g: &Graph<u32, WeightedEdge>,
+        path: &Path,
+        from: NodeIndex,
+        to: NodeIndex,
+    ) {
+        assert_eq!(path.nodes.first(), Some(&from));
+        assert_eq!(path.nodes.last(), Some(&to));
+        let mut cost = 0;
+        for (i, (edge, node)) in path.legs().enumerate() {
+            assert!(g
+                .get_connections(path.nodes[i])
+                .unwrap()
+                .contains(&(edge, node)));
+            cost += g.get_edge(edge).unwrap().cost() as u64;
+        }
+        assert_eq!(path.cost, cost);
+    }
+
+    #[test]
+    fn searches_match_brute_force_on_random_graphs() {
+        let mut rng = fastrand::Rng::with_seed(0x5eed);
+        for _ in 0..300 {
+            let g = random_graph(&mut rng);
+            for (from, _) in g.nodes() {
+                let expected = brute_force_costs(&g, from);
+                for (to, _) in g.nodes() {
+                    let dijkstra = g.dijkstra(from, to);
+                    let astar = g.astar(from, to, |a, b| a.abs_diff(*b));
+                    assert_eq!(dijkstra.as_ref().map(|p| p.cost), expected[to.0]);
+                    assert_eq!(astar.as_ref().map(|p| p.cost), expected[to.0]);
+                    assert_eq!(g.bfs(from, to).is_some(), expected[to.0].is_some());
+                    for path in dijkstra.iter().chain(&astar) {
+                        assert_valid_path(&g, path, from, to);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn trees_and_matrix_match_brute_force() {
+        let mut rng = fastrand::Rng::with_seed(0x7ee5);
+        for _ in 0..100 {
+            let g = random_graph(&mut rng);
+            let matrix = g.distance_matrix();
+            for (from, _) in g.nodes() {
+                let expected = brute_force_costs(&g, from);
+                let tree = g.shortest_path_tree(from);
+                let reachable: Vec<_> = (0..expected.len())
+                    .filter_map(|to| Some((NodeIndex(to), expected[to]?)))
+                    .collect();
+                assert_eq!(tree.distances().collect::<Vec<_>>(), reachable);
+                for (to, _) in g.nodes() {
+                    assert_eq!(tree.distance(to), expected[to.0]);
+                    assert_eq!(matrix.distance(from, to), expected[to.0]);
+                    if let Some(path) = tree.path_to(to) {
+                        assert_valid_path(&g, &path, from, to);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn csr_graph_finds_the_same_costs() {
+        let mut rng = fastrand::Rng::with_seed(0xc5a);
+        for _ in 0..100 {
+            let g = random_graph(&mut rng);
+            let expected = g.distance_matrix();
+            let csr = CsrGraph::from(g);
+            for (from, _) in csr.nodes() {
+                for (to, _) in csr.nodes() {
+                    let path = csr.astar(from, to, |a, b| a.abs_diff(*b));
+                    assert_eq!(path.map(|p| p.cost), expected.distance(from, to));
+                    assert_eq!(
+                        csr.bfs(from, to).is_some(),
+                        expected.distance(from, to).is_some()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn removed_items_keep_their_indices() {
+        let mut g: Graph<String, WeightedEdge> = Graph::new();
+
+        let a = g.insert_node("A".into());
+        let b = g.insert_node("B".into());
+        let c = g.insert_node("C".into());
+
+        let ab = g.insert_edge_undirected(WeightedEdge(1), a, b);
+        let bc = g.insert_edge_undirected(WeightedEdge(1), b, c);
+        let ac = g.insert_edge(WeightedEdge(5), a, c);
+
+        assert_eq!(g.remove_edge(ac).map(|e| e.0), Some(5));
+        assert!(g.get_edge(ac).is_none());
+        assert!(g.remove_edge(ac).is_none());
+        assert_eq!(g.dijkstra(a, c).unwrap().nodes, [a, b, c]);
+
+        assert_eq!(g.remove_node(b).as_deref(), Some("B"));
+        assert!(g.get_node(b).is_none());
+        assert!(g.get_edge(ab).is_none());
+        assert!(g.get_edge(bc).is_none());
+        assert!(g.get_connections(a).unwrap().is_empty());
+        assert!(g.dijkstra(a, c).is_none());
+        assert!(g.bfs(a, b).is_none());
+
+        // Edges to removed or unknown nodes are refused before anything changes.
+        for (from, to) in [(a, b), (b, a), (a, NodeIndex(99))] {
+            let inserted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                g.insert_edge_undirected(WeightedEdge(1), from, to)
+            }));
+            assert!(inserted.is_err());
+        }
+        assert!(g.get_connections(a).unwrap().is_empty());
+        assert!(g.dijkstra(a, a).is_some());
+
+        // New items never reuse a removed index.
+        let d = g.insert_node("D".into());
+        assert_ne!(d, b);
+        assert_eq!(g.nodes().map(|(n, _)| n).collect::<Vec<_>>(), [a, c, d]);
+        assert_eq!(g.get_node(c).map(String::as_str), Some("C"));
+    }
+
+    struct Hours(f32);
+
+    impl Edge<f32> for Hours {
+        fn cost(&self) -> f32 {
+            self.0
+        }
+    }
+
+    /// Cheapest toll first, then the quickest.
+    #[derive(Clone, Copy, PartialEq, PartialOrd, Default, Debug)]
+    struct Fare {
+        toll: u32,
+        hours: f32,
+    }
+
+    impl Add for Fare {
+        type Output = Fare;
+
+        fn add(self, other: Fare) -> Fare {
+            Fare {
+                toll: self.toll + other.toll,
+                hours: self.hours + other.hours,
+            }
+        }
+    }
+
+    impl Cost for Fare {
+        type Total = Fare;
+
+        fn total(self) -> Fare {
+            self
+        }
+    }
+
+    impl Edge<Fare> for Fare {
+        fn cost(&self) -> Fare {
+            *self
+        }
+    }
+
+    #[test]
+    fn fractional_and_composite_costs() {
+        let mut g: Graph<&str, Hours, f32> = Graph::new();
+        let a = g.insert_node("A");
+        let b = g.insert_node("B");
+        let c = g.insert_node("C");
+        g.insert_edge(Hours(2.5), a, b);
+        g.insert_edge(Hours(0.25), b, c);
+        g.insert_edge(Hours(3.0), a, c);
+
+        let path = g.dijkstra(a, c).unwrap();
+        assert_eq!(path.nodes, [a, b, c]);
+        assert_eq!(path.cost, 2.75);
+        assert_eq!(g.distance_matrix().distance(c, a), None);
+        assert_eq!(
+            CsrGraph::from(g).shortest_path_tree(a).distance(b),
+            Some(2.5)
+        );
+
+        let mut g: Graph<&str, Fare, Fare> = Graph::new();
+        let a = g.insert_node("A");
+        let b = g.insert_node("B");
+        let c = g.insert_node("C");
+        let fare = |toll, hours| Fare { toll, hours };
+        g.insert_edge(fare(5, 1.0), a, c);
+        g.insert_edge(fare(0, 4.0), a, b);
+        g.insert_edge(fare(0, 2.5), b, c);
+
+        let path = g.dijkstra(a, c).unwrap();
+        assert_eq!(path.nodes, [a, b, c]);
+        assert_eq!(path.cost, fare(0, 6.5));
+    }
+
+    #[test]
+    fn nan_costs_are_impassable() {
+        let mut g: Graph<(), Hours, f32> = Graph::new();
+        let a = g.insert_node(());
+        let b = g.insert_node(());
+        let c = g.insert_node(());
+        g.insert_edge_undirected(Hours(1.0), a, b);
+        g.insert_edge_undirected(Hours(f32::NAN), b, c);
+        g.insert_edge_undirected(Hours(2.0), c, a);
+
+        let path = g.dijkstra(a, c).unwrap();
+        assert_eq!(path.nodes, [a, c]);
+        assert!(g.dijkstra(b, c).unwrap().cost == 3.0);
+        let tree = g.shortest_path_tree(b);
+        assert_eq!(tree.distance(c), Some(3.0));
+
+        let mut g: Graph<(), Hours, f32> = Graph::new();
+        let a = g.insert_node(());
+        let b = g.insert_node(());
+        g.insert_edge_undirected(Hours(f32::NAN), a, b);
+        assert!(g.dijkstra(a, b).is_none());
+    }
+
+    #[test]
+    fn searches_by_cost_closure() {
+        // Nodes are heights; climbing costs extra, so the same edges are cheap
+        // going down and dear going up.
+        let mut g: Graph<u32, WeightedEdge> = Graph::new();
+        let valley = g.insert_node(0);
+        let pass = g.insert_node(10);
+        let plain = g.insert_node(1);
+        let town = g.insert_node(0);
+        g.insert_edge_undirected(WeightedEdge(3), valley, pass);
+        g.insert_edge_undirected(WeightedEdge(3), pass, town);
+        g.insert_edge_undirected(WeightedEdge(5), valley, plain);
+        g.insert_edge_undirected(WeightedEdge(5), plain, town);
+
+        let climb = |edge: &WeightedEdge, from: NodeIndex, to: NodeIndex| {
+            let (from, to) = (g.get_node(from).unwrap(), g.get_node(to).unwrap());
+            edge.cost() + to.saturating_sub(*from)
+        };
+        assert_eq!(
+            g.dijkstra(valley, town).unwrap().nodes,
+            [valley, pass, town]
+        );
+        let path = g.dijkstra_by(valley, town, climb).unwrap();
+        assert_eq!(path.nodes, [valley, plain, town]);
+        assert_eq!(path.cost, 11);
+        assert_eq!(g.dijkstra_by(pass, town, climb).unwrap().cost, 3);
+        assert_eq!(g.dijkstra_by(town, pass, climb).unwrap().cost, 13);
+
+        // A closure can also steer searches away from nodes by making them too
+        // dear to enter. An infinite cost does not rule a node out: it is still
+        // found when there is no other way there.
+        let avoid_pass = |edge: &WeightedEdge, _, to| {
+            if to == pass {
+                f32::INFINITY
+            } else {
+                edge.cost() as f32 / 2.0
+            }
+        };
+        let csr = CsrGraph::from(g);
+        assert_eq!(csr.dijkstra_by(valley, town, avoid_pass).unwrap().cost, 5.0);
+        let forced = csr.dijkstra_by(valley, pass, avoid_pass).unwrap();
+        assert_eq!(forced.nodes, [valley, pass]);
+        assert_eq!(forced.cost, f64::INFINITY);
+    }
+
+    #[test]
+    fn finds_components_cycles_and_chokepoints() {
+        // Two triangles joined by a single one-way road, and a one-way spur off the second.
+        let mut g = Graph::<&str, WeightedEdge>::new();
+        let [a, b, c, d, e, f, spur] =
+            ["A", "B", "C", "D", "E", "F", "Spur"].map(|n| g.insert_node(n));
+        let lone = g.insert_node("Lone");
+        g.insert_edge_undirected(WeightedEdge(1), a, b);
+        g.insert_edge_undirected(WeightedEdge(1), b, c);
+        g.insert_edge_undirected(WeightedEdge(1), c, a);
+        let cd = g.insert_edge(WeightedEdge(4), c, d);
+        g.insert_edge_undirected(WeightedEdge(1), d, e);
+        g.insert_edge_undirected(WeightedEdge(1), e, f);
+        g.insert_edge_undirected(WeightedEdge(1), f, d);
+        let to_spur = g.insert_edge(WeightedEdge(2), f, spur);
+
+        assert_eq!(
+            g.connected_components(),
+            [vec![a, b, c, d, e, f, spur], vec![lone]]
+        );
+        assert_eq!(
+            g.strongly_connected_components(),
+            [vec![spur], vec![d, e, f], vec![a, b, c], vec![lone]]
+        );
+        assert_eq!(g.articulation_points(), [c, d, f]);
+        assert_eq!(g.bridges(), [cd, to_spur]);
+
+        let cycle = g.find_cycle().unwrap();
+        assert_eq!(cycle.nodes, [a, b, c, a]);
+        assert_eq!(cycle.cost, 3);
+
+        // A tree has no cycles, even though its roads run both ways.
+        let mut tree = Graph::<&str, WeightedEdge>::new();
+        let [root, left, right] = ["Root", "Left", "Right"].map(|n| tree.insert_node(n));
+        tree.insert_edge_undirected(WeightedEdge(1), root, left);
+        tree.insert_edge_undirected(WeightedEdge(1), root, right);
+        assert!(tree.find_cycle().is_none());
+        assert_eq!(tree.articulation_points(), [root]);
+        assert_eq!(tree.bridges().len(), 2);
+        tree.insert_edge(WeightedEdge(1), left, left);
+        assert_eq!(tree.find_cycle().unwrap().nodes, [left, left]);
+    }
+
+    /// Nodes reachable from `from` without using `skip_edge` or entering `skip_node`.
+    fn brute_force_reach(
+        g: &Graph<u32, WeightedEdge>,
+        from: NodeIndex,
+        undirected: bool,
+        skip_edge: Option<EdgeIndex>,
+        skip_node: Option<NodeIndex>,
+    ) -> Vec<bool> {
+        let mut links = Vec::new();
+        for (node, _) in g.nodes() {
+            for (edge, to) in g.get_connections(node).unwrap() {
+                if Some(*edge) == skip_edge || [node, *to].iter().any(|n| Some(*n) == skip_node) {
+                    continue;
+                }
+                links.push((node, *to));
+                if undirected {
+                    links.push((*to, node));
+                }
+            }
+        }
+        let mut reached = vec![false; g.nodes.len()];
+        reached[from.0] = true;
+        loop {
+            let mut changed = false;
+            for (a, b) in &links {
+                if reached[a.0] && !reached[b.0] {
+                    reached[b.0] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                return reached;
+            }
+        }
+    }
+
+    #[test]
+    fn connectivity_matches_brute_force_on_random_graphs() {
+        let mut rng = fastrand::Rng::with_seed(0xc0ffee);
+        for _ in 0..300 {
+            let g = random_graph(&mut rng);
+            let nodes: Vec<NodeIndex> = g.nodes().map(|(n, _)| n).collect();
+            let reach = |from, undirected| brute_force_reach(&g, from, undirected, None, None);
+
+            for (directed, components) in [
+                (false, g.connected_components()),
+                (true, g.strongly_connected_components()),
+            ] {
+                let mut seen: Vec<NodeIndex> = components.concat();
+                seen.sort_by_key(|n| n.0);
+                assert_eq!(seen, nodes);
+                for component in &components {
+                    for a in &nodes {
+                        let same = reach(component[0], !directed)[a.0]
+                            && reach(*a, !directed)[component[0].0];
+                        assert_eq!(component.contains(a), same);
+                    }
+                }
+                if directed {
+                    // Nothing reaches a component listed after it.
+                    for (i, earlier) in components.iter().enumerate() {
+                        for later in &components[i + 1..] {
+                            assert!(!reach(earlier[0], false)[later[0].0]);
+                        }
+                    }
+                }
+            }
+
+            let bridges = g.bridges();
+            for (node, _) in g.nodes() {
+                for (edge, to) in g.get_connections(node).unwrap() {
+                    let split = !brute_force_reach(&g, node, true, Some(*edge), None)[to.0];
+                    assert_eq!(bridges.contains(edge), split);
+                }
+            }
+            let points = g.articulation_points();
+            for node in &nodes {
+                let around: Vec<NodeIndex> = nodes
+                    .iter()
+                    .copied()
+                    .filter(|n| n != node && (reach(*node, false)[n.0] || reach(*n, false)[node.0]))
+                    .filter(|n| {
+                        let touches = |a: NodeIndex, b: NodeIndex| {
+                            g.get_connections(a).unwrap().iter().any(|(_, to)| *to == b)
+                        };
+                        touches(*node, *n) || touches(*n, *node)
+                    })
+                    .collect();
+                let split = around
+                    .iter()
+                    .any(|n| !brute_force_reach(&g, around[0], true, None, Some(*node))[n.0]);
+                assert_eq!(points.contains(node), split);
+            }
+
+            let has_cycle = g.nodes().any(|(node, _)| {
+                g.get_connections(node)
+                    .unwrap()
+                    .iter()
+                    .any(|(edge, to)| brute_force_reach(&g, *to, false, Some(*edge), None)[node.0])
+            });
+            match g.find_cycle() {
+                Some(cycle) => {
+                    let start = cycle.nodes[0];
+                    assert_valid_path(&g, &cycle, start, start);
+                    let mut edges = cycle.edges.clone();
+                    edges.sort_by_key(|e| e.0);
+                    edges.dedup();
+                    assert_eq!(edges.len(), cycle.edges.len());
+                    assert!(!edges.is_empty());
+                }
+                None => assert!(!has_cycle),
+            }
+            let csr = CsrGraph::from(g);
+            assert_eq!(csr.bridges(), bridges);
+            assert_eq!(csr.articulation_points(), points);
+        }
+    }
+
+    #[test]
+    fn neighbors_keep_insertion_order() {
+        let mut g = Graph::<String, WeightedEdge>::new();
+        let a = g.insert_node("A".into());
+        let d = g.insert_node("D".into());
+        let c = g.insert_node("C".into());
+        let b = g.insert_node("B".into());
+        let e = g.insert_node("E".into());
+
+        // Two routes of equal length; the first inserted wins the tie.
+        let ad = g.insert_edge(WeightedEdge(1), a, d);
+        let ab = g.insert_edge(WeightedEdge(1), a, b);
+        let ac = g.insert_edge(WeightedEdge(2), a, c);
+        g.insert_edge(WeightedEdge(1), d, e);
+        g.insert_edge(WeightedEdge(1), b, e);
+        let aa = g.insert_edge_undirected(WeightedEdge(1), a, a);
+
+        assert_eq!(
+            g.get_connections(a).unwrap(),
+            [(ad, d), (ab, b), (ac, c), (aa, a)]
+        );
+        assert_eq!(g.bfs(a, e).unwrap().nodes, [a, d, e]);
+        assert_eq!(g.dijkstra(a, e).unwrap().nodes, [a, d, e]);
+        assert_eq!(
+            g.to_mermaid(),
+            "graph TD;\n\t0-->|1|1;\n\t0-->|1|3;\n\t0-->|2|2;\n\t0-->|1|0;\n\t1-->|1|4;\n\t3-->|1|4;"
+        );
+
+        g.remove_edge(ad);
+        assert_eq!(g.get_connections(a).unwrap(), [(ab, b), (ac, c), (aa, a)]);
+        assert_eq!(g.dijkstra(a, e).unwrap().nodes, [a, b, e]);
+        assert_eq!(
+            CsrGraph::from(g).get_connections(a),
+            [(ab, b), (ac, c), (aa, a)]
+        );
+    }
+
     #[test]
     fn test_insert_node_and_get_node() {
         let mut graph = Graph::<String, EmptyEdge>::new();
@@ -536,6 +1454,7 @@ mod tests {
         let path_str = graph
             .dijkstra(node_a, node_d)
             .expect("dijkstra did not resolve")
+            .nodes
             .iter()
             .map(|n| graph.get_node(*n).unwrap().clone())
             .collect::<Vec<String>>()
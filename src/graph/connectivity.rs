@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use super::{Cost, Edge, EdgeIndex, EdgePair, NodeIndex, Path, Search, TotalCost};
+
+/**
+    Structural questions about a graph: which nodes hang together, where it loops
+    and which nodes and edges everything else depends on. Available on every graph
+    that supports `Search`.
+
+    Results list nodes and edges in index order, so they do not change between runs.
+*/
+pub trait Connectivity: Search {
+    /**
+        Groups of nodes joined by edges in either direction, each sorted by index
+        and ordered by their first node.
+    */
+    fn connected_components(&self) -> Vec<Vec<NodeIndex>> {
+        let adjacency = undirected(self);
+        let mut seen = vec![false; adjacency.len()];
+        let mut components = Vec::new();
+        for root in node_indices(self) {
+            if seen[root.0] {
+                continue;
+            }
+            seen[root.0] = true;
+            let mut component = vec![root];
+            let mut frontier = vec![root];
+            while let Some(node) = frontier.pop() {
+                for (_, next) in &adjacency[node.0] {
+                    if !seen[next.0] {
+                        seen[next.0] = true;
+                        component.push(*next);
+                        frontier.push(*next);
+                    }
+                }
+            }
+            component.sort_by_key(|node| node.0);
+            components.push(component);
+        }
+        components
+    }
+
+    /**
+        Groups of nodes that can all reach each other following edge directions,
+        each sorted by index. A component is listed after every component it can
+        reach.
+    */
+    fn strongly_connected_components(&self) -> Vec<Vec<NodeIndex>> {
+        let bound = self.node_bound();
+        let mut order: Vec<Option<usize>> = vec![None; bound];
+        let mut low = vec![0; bound];
+        let mut on_stack = vec![false; bound];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut counter = 0;
+
+        for root in node_indices(self) {
+            if order[root.0].is_some() {
+                continue;
+            }
+            order[root.0] = Some(counter);
+            low[root.0] = counter;
+            counter += 1;
+            stack.push(root);
+            on_stack[root.0] = true;
+            let mut dfs = vec![(root, self.neighbors(root))];
+
+            while let Some((node, neighbors)) = dfs.last_mut() {
+                let node = *node;
+                if let Some((_, next)) = neighbors.next() {
+                    match order[next.0] {
+                        None => {
+                            order[next.0] = Some(counter);
+                            low[next.0] = counter;
+                            counter += 1;
+                            stack.push(next);
+                            on_stack[next.0] = true;
+                            dfs.push((next, self.neighbors(next)));
+                        }
+                        Some(index) if on_stack[next.0] => low[node.0] = low[node.0].min(index),
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                dfs.pop();
+                if let Some((parent, _)) = dfs.last() {
+                    low[parent.0] = low[parent.0].min(low[node.0]);
+                }
+                if order[node.0] == Some(low[node.0]) {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member.0] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    component.sort_by_key(|node| node.0);
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /**
+        A closed path that uses no edge twice, following edge directions, or `None`
+        if the graph has no cycles. The path starts and ends at the same node.
+        Going back and forth along one undirected edge does not count.
+    */
+    fn find_cycle(&self) -> Option<Path<TotalCost<Self>>> {
+        // Edges listed from both ends can be taken either way.
+        let mut listings: HashMap<EdgeIndex, u32> = HashMap::new();
+        for node in node_indices(self) {
+            for (edge, next) in self.neighbors(node) {
+                if next != node {
+                    *listings.entry(edge).or_default() += 1;
+                }
+            }
+        }
+        let two_way = |edge: EdgeIndex| listings.get(&edge) == Some(&2);
+
+        // Cycles made only of two way edges, found by a depth first search that
+        // never turns straight back along the edge it came in by.
+        #[derive(Clone, Copy, PartialEq)]
+        enum Visit {
+            New,
+            Open,
+            Done,
+        }
+        let mut visits = vec![Visit::New; self.node_bound()];
+        // (edge, from) each node was first reached by.
+        let mut came_from: Vec<Option<EdgePair>> = vec![None; self.node_bound()];
+        for root in node_indices(self) {
+            if visits[root.0] != Visit::New {
+                continue;
+            }
+            visits[root.0] = Visit::Open;
+            let mut dfs = vec![(root, None, self.neighbors(root))];
+
+            while let Some((node, entered_by, neighbors)) = dfs.last_mut() {
+                let (node, entered_by) = (*node, *entered_by);
+                let Some((edge, next)) = neighbors.next() else {
+                    visits[node.0] = Visit::Done;
+                    dfs.pop();
+                    continue;
+                };
+                if Some(edge) == entered_by || !two_way(edge) {
+                    continue;
+                }
+                match visits[next.0] {
+                    Visit::New => {
+                        visits[next.0] = Visit::Open;
+                        came_from[next.0] = Some((edge, node));
+                        dfs.push((next, Some(edge), self.neighbors(next)));
+                    }
+                    // `next` is still being explored, so it leads round to `node`.
+                    Visit::Open => {
+                        let mut cycle =
+                            self.trace_path(next, node, |node| came_from[node.0].unwrap());
+                        cycle.cost = cycle.cost + self.edge(edge).unwrap().cost().total();
+                        cycle.nodes.push(next);
+                        cycle.edges.push(edge);
+                        return Some(cycle);
+                    }
+                    Visit::Done => {}
+                }
+            }
+        }
+
+        // Any other cycle takes a one way edge whose ends can reach each other.
+        let mut component = vec![usize::MAX; self.node_bound()];
+        for (index, members) in self.strongly_connected_components().iter().enumerate() {
+            for node in members {
+                component[node.0] = index;
+            }
+        }
+        for node in node_indices(self) {
+            for (edge, next) in self.neighbors(node) {
+                if two_way(edge) || component[node.0] != component[next.0] {
+                    continue;
+                }
+                // The shortest way back cannot use `edge`, which leaves `node`.
+                let back = self.bfs(next, node).unwrap();
+                let mut nodes = vec![node];
+                nodes.extend(back.nodes);
+                let mut edges = vec![edge];
+                edges.extend(back.edges);
+                return Some(Path {
+                    nodes,
+                    edges,
+                    cost: self.edge(edge).unwrap().cost().total() + back.cost,
+                });
+            }
+        }
+        None
+    }
+
+    /**
+        Nodes whose removal would split their connected component, ignoring edge
+        directions. Every path between the parts runs through them.
+    */
+    fn articulation_points(&self) -> Vec<NodeIndex> {
+        cut_points(self).0
+    }
+
+    /**
+        Edges whose removal would split their connected component, ignoring edge
+        directions. They are the only link between the two parts.
+    */
+    fn bridges(&self) -> Vec<EdgeIndex> {
+        cut_points(self).1
+    }
+}
+
+impl<S: Search + ?Sized> Connectivity for S {}
+
+fn node_indices<S: Search + ?Sized>(graph: &S) -> impl Iterator<Item = NodeIndex> + '_ {
+    (0..graph.node_bound())
+        .map(NodeIndex)
+        .filter(|node| graph.node(*node).is_some())
+}
+
+/// Every edge listed from both of its ends, once each.
+fn undirected<S: Search + ?Sized>(graph: &S) -> Vec<Vec<EdgePair>> {
+    let mut adjacency = vec![Vec::new(); graph.node_bound()];
+    for node in node_indices(graph) {
+        for (edge, next) in graph.neighbors(node) {
+            adjacency[node.0].push((edge, next));
+            adjacency[next.0].push((edge, node));
+        }
+    }
+    for pairs in adjacency.iter_mut() {
+        pairs.sort_by_key(|(edge, node)| (edge.0, node.0));
+        pairs.dedup();
+    }
+    adjacency
+}
+
+/**
+    Articulation points and bridges, from one depth first search that tracks the
+    earliest discovered node each subtree can reach without its parent edge.
+*/
+fn cut_points<S: Search + ?Sized>(graph: &S) -> (Vec<NodeIndex>, Vec<EdgeIndex>) {
+    let adjacency = undirected(graph);
+    let mut order: Vec<Option<usize>> = vec![None; adjacency.len()];
+    let mut low = vec![0; adjacency.len()];
+    let mut is_cut = vec![false; adjacency.len()];
+    let mut bridges = Vec::new();
+    let mut counter = 0;
+
+    for root in node_indices(graph) {
+        if order[root.0].is_some() {
+            continue;
+        }
+        order[root.0] = Some(counter);
+        low[root.0] = counter;
+        counter += 1;
+        let mut root_children = 0;
+        // (node, edge it was entered by, position in its adjacency list)
+        let mut dfs: Vec<(NodeIndex, Option<EdgeIndex>, usize)> = vec![(root, None, 0)];
+
+        while let Some((node, entered_by, position)) = dfs.last_mut() {
+            let node = *node;
+            if let Some((edge, next)) = adjacency[node.0].get(*position).copied() {
+                *position += 1;
+                if Some(edge) == *entered_by {
+                    continue;
+                }
+                match order[next.0] {
+                    None => {
+                        order[next.0] = Some(counter);
+                        low[next.0] = counter;
+                        counter += 1;
+                        if node == root {
+                            root_children += 1;
+                        }
+                        dfs.push((next, Some(edge), 0));
+                    }
+                    Some(index) => low[node.0] = low[node.0].min(index),
+                }
+                continue;
+            }
+
+            let entered_by = *entered_by;
+            dfs.pop();
+            let (Some((parent, _, _)), Some(edge)) = (dfs.last(), entered_by) else {
+                continue;
+            };
+            let parent_order = order[parent.0].unwrap();
+            low[parent.0] = low[parent.0].min(low[node.0]);
+            if low[node.0] > parent_order {
+                bridges.push(edge);
+            }
+            if *parent != root && low[node.0] >= parent_order {
+                is_cut[parent.0] = true;
+            }
+        }
+        if root_children > 1 {
+            is_cut[root.0] = true;
+        }
+    }
+
+    let points = node_indices(graph).filter(|node| is_cut[node.0]).collect();
+    bridges.sort_by_key(|edge| edge.0);
+    (points, bridges)
+}
@@ -1,4 +1,5 @@
-#![feature(test)]
+#![cfg_attr(test, feature(test))]
+pub mod cli;
 pub mod game;
 pub mod graph;
 pub mod menu;
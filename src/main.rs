@@ -1,29 +1,85 @@
 use dialoguer::Confirm;
-use dialoguer::{console::Term, Select};
+use dialoguer::{console::Term, Input, Select};
 use fastrand::Rng;
 use indicatif::{ProgressBar, ProgressDrawTarget};
-use rust_trader::game::city::{City, RoadData};
-use rust_trader::game::item::ItemDatabase;
+use rust_trader::cli::{Args, USAGE};
+use rust_trader::game::bandit::{self, Ambush, AmbushResponse};
+use rust_trader::game::clock::{Clock, HOURS_PER_DAY};
+use rust_trader::game::crafting::{self, Recipe};
+use rust_trader::game::event::EventContext;
+use rust_trader::game::food::{self, Hunger};
+use rust_trader::game::item::{ItemDatabase, ItemIndex, ItemType};
+use rust_trader::game::ledger::TransactionKind;
+use rust_trader::game::market;
 use rust_trader::game::player::PlayerState;
-use rust_trader::game::world::deserialize_world;
-use rust_trader::graph::{Edge, EdgeIndex, Graph, NodeIndex};
+use rust_trader::game::route::{self, RouteCost, RouteKind};
+use rust_trader::game::save::{self, SaveData, SaveError};
+use rust_trader::game::world::World;
+use rust_trader::graph::{Edge, EdgeIndex, EdgePair, NodeIndex, Path};
 use rust_trader::menu::{prompt_menu, MenuItem};
-use std::collections::HashMap;
-use std::rc::Rc;
+use std::fs;
+use std::process;
 use std::thread;
 use std::time::Duration;
 
 struct GameState {
-    world_map: Graph<City, RoadData>,
-    item_database: ItemDatabase,
+    world: World,
     term: Term,
     player: PlayerState,
     rng: Rng,
+    clock: Clock,
+    save_slot: String,
+    /// Set when the caravan starves, ending the game.
+    starved: bool,
 }
 
-fn travel_to(state: &mut GameState, chosen_edge_idx: EdgeIndex, chosen_node_idx: NodeIndex) {
+/// How a trip down a single road ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Journey {
+    Arrived,
+    /// The player could not pay the toll and never left.
+    TurnedBack,
+    /// The caravan starved on the road.
+    Starved,
+}
+
+/// Time a single trade at the market takes.
+const TRADE_HOURS: u32 = 1;
+/// Number of transactions shown on the ledger screen.
+const LEDGER_PAGE: usize = 20;
+
+/// Travels down a single road.
+fn travel_to(
+    state: &mut GameState,
+    chosen_edge_idx: EdgeIndex,
+    chosen_node_idx: NodeIndex,
+) -> Journey {
+    let toll = state.world.map.get_edge(chosen_edge_idx).unwrap().toll;
+    if toll > 0
+        && state
+            .player
+            .transact(
+                state.clock.day(),
+                TransactionKind::Toll,
+                -(toll as i64),
+                None,
+                &state.world.items,
+            )
+            .is_none()
+    {
+        state
+            .term
+            .write_line(&format!(
+                "You cannot afford the {} gold toll for this road.",
+                toll
+            ))
+            .unwrap();
+        thread::sleep(Duration::from_secs(2));
+        return Journey::TurnedBack;
+    }
+
     let player_location = &state.player.position;
-    let world_map = &state.world_map;
+    let world_map = &state.world.map;
     let term = &state.term;
     let current_city = world_map.get_node(*player_location).unwrap();
 
@@ -38,8 +94,10 @@ fn travel_to(state: &mut GameState, chosen_edge_idx: EdgeIndex, chosen_node_idx:
     let progress = ProgressBar::new(100);
     progress.set_draw_target(ProgressDrawTarget::term(term.clone(), 60));
 
-    let miles_to_travel = chosen_edge.cost();
+    let mut miles_to_travel = chosen_edge.cost();
     let mut miles_traveled = 0;
+    let mut hours_on_road = 0.0;
+    let mut meals = 0;
 
     term.clear_screen().unwrap();
     term.write_line(&prompt).unwrap();
@@ -52,111 +110,929 @@ fn travel_to(state: &mut GameState, chosen_edge_idx: EdgeIndex, chosen_node_idx:
     )
     .unwrap();
 
-    for _ in 0..miles_to_travel {
+    while miles_traveled < miles_to_travel {
         thread::sleep(Duration::from_millis(60));
         miles_traveled += 1;
-        let event_odds = state.rng.u32(0..200);
-        if event_odds == 0 {
+        let ctx = EventContext {
+            road: chosen_edge,
+            from: current_city,
+            to: chosen_node,
+            player: &state.player,
+            season: state.clock.season(),
+        };
+        if let Some(event) = state.world.events.roll(&mut state.rng, &ctx) {
+            let choices = event.available_choices(&ctx);
+            let labels: Vec<&str> = choices.iter().map(|c| c.label.as_str()).collect();
+            let choice = progress.suspend(|| {
+                Select::new()
+                    .with_prompt(&event.prompt)
+                    .items(&labels)
+                    .default(0)
+                    .interact_on(term)
+                    .expect("Chose invalid option")
+            });
+            let choice = choices[choice];
+            let day = state.clock.day() + meals;
+            let consequences = choice.apply(&mut state.player, &state.world.items, day);
+            miles_to_travel += consequences.detour_miles;
+            hours_on_road += (consequences.delay_days * HOURS_PER_DAY) as f32;
+            if let Some(message) = &choice.message {
+                progress.suspend(|| term.write_line(message).unwrap());
+            }
+        }
+
+        if let Some(ambush) =
+            bandit::roll_ambush(&mut state.rng, chosen_edge.danger, state.player.guards)
+        {
+            let day = state.clock.day() + meals;
             progress.suspend(|| {
-                let a = Confirm::new()
-                    .with_prompt("You see a homeless dude. Do you give him a nickel?")
-                    .interact_on(&term)
-                    .unwrap();
-                if a {
-                    state
-                        .player
-                        .inventory
-                        .add_item(*state.item_database.get_index("berries").unwrap(), 3);
-                    println!("{:?}", state.player.inventory);
+                face_ambush(
+                    term,
+                    ambush,
+                    &mut state.player,
+                    &state.world.items,
+                    &mut state.rng,
+                    day,
+                )
+            });
+        }
+
+        // Hungry caravans and heavy carts cover fewer miles in a day.
+        let pace = route::miles_per_day(&state.player, &state.world.items);
+        hours_on_road += HOURS_PER_DAY as f32 / pace;
+        while hours_on_road >= ((meals + 1) * HOURS_PER_DAY) as f32 {
+            meals += 1;
+            let today = state.clock.day() + meals;
+            let spoiled = state.player.inventory.advance(today, &state.world.items);
+            if !spoiled.is_empty() {
+                progress.suspend(|| {
+                    term.write_line(&spoilage_message(&spoiled, &state.world.items))
+                        .unwrap()
+                });
+            }
+            food::eat(&mut state.player, &state.world.items);
+            match state.player.hunger() {
+                Hunger::Fed => {}
+                Hunger::Hungry { days } => progress.suspend(|| {
+                    term.write_line(&format!(
+                        "Your caravan has gone {} days without a full meal and slows down.",
+                        days
+                    ))
+                    .unwrap()
+                }),
+                Hunger::Starved => {
+                    progress.abandon();
+                    // The world moves on for the days spent on the road.
+                    pass_time(state, hours_on_road.ceil() as u32);
+                    return Journey::Starved;
                 }
-            })
+            }
         }
 
         progress.set_position(((miles_traveled as f32 / miles_to_travel as f32) * 100.0) as u64);
     }
     progress.finish();
 
+    let hours = hours_on_road.ceil() as u32;
+    let days = hours.div_ceil(HOURS_PER_DAY);
+    let deserted = bandit::pay_guards(
+        &mut state.player,
+        days,
+        &state.world.items,
+        state.clock.day(),
+    );
+    if deserted > 0 {
+        state
+            .term
+            .write_line(&format!(
+                "{} guards leave your service because you could not pay them.",
+                deserted
+            ))
+            .unwrap();
+        thread::sleep(Duration::from_secs(2));
+    }
+    pass_time(state, hours);
     state.player.goto(chosen_node_idx);
+    Journey::Arrived
 }
 
-fn main() {
-    let world =
-        deserialize_world(include_str!("../data/world.toml")).expect("Could not read world file");
-    // print!("{:?}", world);
-    let mut world_map: Graph<City, RoadData> = Graph::new();
-    let mut cities: HashMap<String, NodeIndex> = HashMap::new();
-    for city in &world.cities {
-        let idx = world_map.insert_node(city.into());
-        cities.insert(city.name.clone(), idx);
-    }
-
-    for city in &world.cities {
-        for road in &city.roads {
-            let conn_a = cities.get(&city.name).expect(
-                format!("Could not find city {} for road to {}", city.name, road.to).as_str(),
+fn face_ambush(
+    term: &Term,
+    ambush: Ambush,
+    player: &mut PlayerState,
+    items: &ItemDatabase,
+    rng: &mut Rng,
+    day: u32,
+) {
+    let mut responses = Vec::new();
+    if player.gold >= ambush.tribute() {
+        responses.push((
+            format!("Pay the {} gold they demand", ambush.tribute()),
+            AmbushResponse::Pay,
+        ));
+    }
+    responses.push(("Make a run for it".to_string(), AmbushResponse::Flee));
+    if player.guards > 0 {
+        responses.push((
+            format!("Fight them off with your {} guards", player.guards),
+            AmbushResponse::Fight,
+        ));
+    }
+    responses.push(("Hand over the goods".to_string(), AmbushResponse::Surrender));
+
+    let labels: Vec<&str> = responses.iter().map(|(label, _)| label.as_str()).collect();
+    let choice = Select::new()
+        .with_prompt(format!("{} bandits block the road!", ambush.bandits))
+        .items(&labels)
+        .default(0)
+        .interact_on(term)
+        .expect("Chose invalid option");
+    let outcome = ambush.resolve(responses[choice].1, player, items, rng, day);
+
+    if outcome.escaped {
+        term.write_line("You leave the bandits behind with your cargo intact.")
+            .unwrap();
+    } else {
+        let stolen: Vec<String> = outcome
+            .stolen
+            .iter()
+            .map(|(item, qty)| format!("{} {}", qty, items.get_item(*item).unwrap().name))
+            .collect();
+        if stolen.is_empty() {
+            term.write_line("The bandits find nothing worth taking.")
+                .unwrap();
+        } else {
+            term.write_line(&format!("The bandits make off with {}.", stolen.join(", ")))
+                .unwrap();
+        }
+        if outcome.guards_lost > 0 {
+            term.write_line("One of your guards falls in the fight.")
+                .unwrap();
+        }
+    }
+    thread::sleep(Duration::from_secs(2));
+}
+
+/// Whole days, rounded up, an estimate of hours on the road takes.
+fn hours_to_days(hours: f32) -> u32 {
+    (hours / HOURS_PER_DAY as f32).ceil() as u32
+}
+
+/// Miles and gold in tolls along a planned route.
+fn route_totals(state: &GameState, path: &Path<RouteCost>) -> (u32, u32) {
+    path.edges
+        .iter()
+        .map(|edge| state.world.map.get_edge(*edge).unwrap())
+        .fold((0, 0), |(miles, tolls), road| {
+            (miles + road.distance, tolls + road.toll)
+        })
+}
+
+/// How dangerous a road is, and whether it is the only way between two parts of the map.
+fn road_label(world: &World, edge: EdgeIndex) -> String {
+    let road = world.map.get_edge(edge).unwrap();
+    if world.chokepoints.contains(&edge) {
+        format!("{}, chokepoint", danger_label(road.danger))
+    } else {
+        danger_label(road.danger).to_string()
+    }
+}
+
+fn danger_label(danger: f32) -> &'static str {
+    if danger < 0.15 {
+        "safe"
+    } else if danger < 0.4 {
+        "risky"
+    } else {
+        "dangerous"
+    }
+}
+
+/// Moves the clock forward and lets every city produce and trade in the meantime.
+fn pass_time(state: &mut GameState, hours: u32) {
+    let days = state.clock.advance_hours(hours);
+    let today = state.clock.day();
+    // Goods cities restock or make today are dated today.
+    for city in state.world.map.nodes_mut() {
+        city.inventory.day = today;
+    }
+    market::advance_markets(&mut state.world.map, days, state.clock.season());
+    crafting::advance_production(
+        &mut state.world.map,
+        &state.world.recipes,
+        &state.world.items,
+        days,
+    );
+
+    let spoiled = state.player.inventory.advance(today, &state.world.items);
+    if !spoiled.is_empty() {
+        state
+            .term
+            .write_line(&spoilage_message(&spoiled, &state.world.items))
+            .unwrap();
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn spoilage_message(spoiled: &[(ItemIndex, u32)], items: &ItemDatabase) -> String {
+    let goods: Vec<String> = spoiled
+        .iter()
+        .map(|(item, qty)| format!("{} {}", qty, items.get_item(*item).unwrap().name))
+        .collect();
+    format!("Some of your goods have spoiled: {}.", goods.join(", "))
+}
+
+fn travel_menu(state: &mut GameState) {
+    let edge;
+    let node;
+    {
+        let connections: Vec<_> = state
+            .world
+            .map
+            .get_connections(state.player.position)
+            .unwrap()
+            .iter()
+            .collect();
+        let options: Vec<_> = connections
+            .iter()
+            .map(|(edge_idx, node_idx)| {
+                let edge = state.world.map.get_edge(*edge_idx).unwrap();
+                let node = state.world.map.get_node(*node_idx).unwrap();
+                let toll = if edge.toll > 0 {
+                    format!(", {} gold toll", edge.toll)
+                } else {
+                    String::new()
+                };
+                format!(
+                    "{} to {} ({} miles, {} days{}, {})",
+                    edge.name,
+                    node.name,
+                    edge.distance,
+                    hours_to_days(route::travel_hours(edge, &state.player, &state.world.items)),
+                    toll,
+                    road_label(&state.world, *edge_idx)
+                )
+            })
+            .collect();
+        let prompt = format!(
+            "You carry food for {} days for a caravan of {}. What road would you like to take now?",
+            food::days_of_food(&state.player, &state.world.items),
+            state.player.caravan_size()
+        );
+        let choice = Select::new()
+            .with_prompt(prompt)
+            .items(&options)
+            .default(0)
+            .interact_on(&state.term)
+            .expect("Chose invalid option");
+        let (e, n) = connections.get(choice).unwrap();
+        edge = *e;
+        node = *n;
+    }
+
+    if travel_to(state, edge, node) == Journey::Starved {
+        state.starved = true;
+    }
+}
+
+fn plan_route_menu(state: &mut GameState) {
+    let here = state.player.position;
+    let destinations: Vec<_> = state
+        .world
+        .map
+        .nodes()
+        .filter(|(node, _)| *node != here)
+        .collect();
+    let mut options: Vec<_> = destinations
+        .iter()
+        .map(|(_, city)| city.name.clone())
+        .collect();
+    options.push("Cancel".to_string());
+    let choice = Select::new()
+        .with_prompt("Where would you like to go?")
+        .items(&options)
+        .default(0)
+        .interact_on(&state.term)
+        .expect("Chose invalid option");
+    let Some((destination, _)) = destinations.get(choice).copied() else {
+        return;
+    };
+
+    let mut routes: Vec<(RouteKind, Path<RouteCost>)> = Vec::new();
+    for kind in RouteKind::ALL {
+        let Some(path) = route::plan_route(&state.world, &state.player, destination, kind) else {
+            continue;
+        };
+        // Only offer routes that actually differ.
+        if !routes.iter().any(|(_, other)| other.edges == path.edges) {
+            routes.push((kind, path));
+        }
+    }
+    if routes.is_empty() {
+        state.term.write_line("No road leads there.").unwrap();
+        thread::sleep(Duration::from_secs(2));
+        return;
+    }
+    let mut options: Vec<_> = routes
+        .iter()
+        .map(|(kind, path)| {
+            let via: Vec<&str> = path.nodes[1..path.nodes.len() - 1]
+                .iter()
+                .map(|node| state.world.map.get_node(*node).unwrap().name.as_str())
+                .collect();
+            let via = if via.is_empty() {
+                "direct".to_string()
+            } else {
+                format!("via {}", via.join(", "))
+            };
+            let (miles, tolls) = route_totals(state, path);
+            format!(
+                "The {} route, {} ({} miles, about {} days, {} gold in tolls)",
+                kind,
+                via,
+                miles,
+                hours_to_days(path.cost.hours),
+                tolls
+            )
+        })
+        .collect();
+    options.push("Cancel".to_string());
+    let choice = Select::new()
+        .with_prompt("Which way would you like to go?")
+        .items(&options)
+        .default(0)
+        .interact_on(&state.term)
+        .expect("Chose invalid option");
+    let Some((_, path)) = routes.get(choice) else {
+        return;
+    };
+    let legs: Vec<EdgePair> = path.legs().collect();
+
+    for (edge, node) in &legs {
+        let road = state.world.map.get_edge(*edge).unwrap();
+        let city = state.world.map.get_node(*node).unwrap();
+        state
+            .term
+            .write_line(&format!(
+                "  {} to {} ({} miles, {})",
+                road.name,
+                city.name,
+                road.distance,
+                road_label(&state.world, *edge)
+            ))
+            .unwrap();
+    }
+    let (miles, tolls) = route_totals(state, path);
+    let set_off = Confirm::new()
+        .with_prompt(format!(
+            "{} miles in about {} days, with {} gold in tolls. Set off?",
+            miles,
+            hours_to_days(path.cost.hours),
+            tolls
+        ))
+        .default(true)
+        .interact_on(&state.term)
+        .unwrap();
+    if !set_off {
+        return;
+    }
+
+    for (leg, (edge, node)) in legs.iter().enumerate() {
+        match travel_to(state, *edge, *node) {
+            Journey::Arrived => {}
+            Journey::TurnedBack => return,
+            Journey::Starved => {
+                state.starved = true;
+                return;
+            }
+        }
+        let Some((_, next)) = legs.get(leg + 1) else {
+            break;
+        };
+        let here = state.world.map.get_node(*node).unwrap();
+        let next = state.world.map.get_node(*next).unwrap();
+        let carry_on = Confirm::new()
+            .with_prompt(format!(
+                "You have reached {}. Continue on to {}?",
+                here.name, next.name
+            ))
+            .default(true)
+            .interact_on(&state.term)
+            .unwrap();
+        if !carry_on {
+            return;
+        }
+    }
+}
+
+fn market_menu(state: &mut GameState) {
+    loop {
+        let city = state.world.map.get_node(state.player.position).unwrap();
+        let load = match state.player.inventory.capacity {
+            Some(capacity) => format!(
+                " You are carrying {}/{}.",
+                state.player.inventory.weight(&state.world.items),
+                capacity
+            ),
+            None => String::new(),
+        };
+        let prompt = format!(
+            "Welcome to the {} market. You have {} gold.{}",
+            city.name, state.player.gold, load
+        );
+        let choice = Select::new()
+            .with_prompt(prompt)
+            .items(&["Buy", "Sell", "Leave"])
+            .default(0)
+            .interact_on(&state.term)
+            .expect("Chose invalid option");
+        match choice {
+            0 => buy_menu(state),
+            1 => sell_menu(state),
+            _ => return,
+        }
+    }
+}
+
+/// Asks for an amount up to `max`, showing `too_many` when the player asks for more.
+fn prompt_quantity(term: &Term, max: u32, too_many: &str) -> u32 {
+    Input::<u32>::new()
+        .with_prompt(format!("How many? (0-{})", max))
+        .default(1.min(max))
+        .validate_with(|amount: &u32| {
+            if *amount <= max {
+                Ok(())
+            } else {
+                Err(too_many.to_string())
+            }
+        })
+        .interact_text_on(term)
+        .expect("Invalid quantity")
+}
+
+/// Item name followed by its tags, e.g. "Bread [Edible, Craftible]".
+fn item_label(item: &ItemType) -> String {
+    if item.tags.is_empty() {
+        return item.name.clone();
+    }
+    let tags: Vec<String> = item.tags.iter().map(|tag| tag.to_string()).collect();
+    format!("{} [{}]", item.name, tags.join(", "))
+}
+
+/// Where else the item sells best, to compare against the price here.
+fn price_elsewhere(state: &GameState, item: ItemIndex) -> String {
+    match state.world.best_market_for(item, state.player.position) {
+        Some((node, price, miles)) => format!(
+            "; sells for {} in {}, {} miles away",
+            price,
+            state.world.map.get_node(node).unwrap().name,
+            miles
+        ),
+        None => String::new(),
+    }
+}
+
+fn buy_menu(state: &mut GameState) {
+    let city = state.world.map.get_node(state.player.position).unwrap();
+    let listings: Vec<ItemIndex> = city
+        .market
+        .listings()
+        .iter()
+        .copied()
+        .filter(|item| city.inventory.quantity(*item) > 0)
+        .collect();
+    if listings.is_empty() {
+        state
+            .term
+            .write_line("There is nothing for sale here.")
+            .unwrap();
+        return;
+    }
+    let options: Vec<_> = listings
+        .iter()
+        .map(|item| {
+            let item_type = state.world.items.get_item(*item).unwrap();
+            format!(
+                "{} - {} gold ({} in stock{})",
+                item_label(item_type),
+                market::buy_price(city, &state.world.items, *item).unwrap(),
+                city.inventory.quantity(*item),
+                price_elsewhere(state, *item)
+            )
+        })
+        .collect();
+    let choice = Select::new()
+        .with_prompt("What would you like to buy?")
+        .items(&options)
+        .default(0)
+        .interact_on(&state.term)
+        .expect("Chose invalid option");
+    let item = listings[choice];
+    let stock = city.inventory.quantity(item);
+    let amount = prompt_quantity(
+        &state.term,
+        stock,
+        &format!("You can only trade up to {}", stock),
+    );
+    if amount == 0 {
+        return;
+    }
+
+    let city = state.world.map.get_node_mut(state.player.position).unwrap();
+    let message = match market::buy(
+        city,
+        &mut state.player,
+        &state.world.items,
+        item,
+        amount,
+        state.clock.day(),
+    ) {
+        Ok(total) => {
+            pass_time(state, TRADE_HOURS);
+            format!("You paid {} gold.", total)
+        }
+        Err(err) => err.to_string(),
+    };
+    state.term.write_line(&message).unwrap();
+}
+
+fn sell_menu(state: &mut GameState) {
+    let city = state.world.map.get_node(state.player.position).unwrap();
+    let sellable: Vec<ItemIndex> = city
+        .market
+        .listings()
+        .iter()
+        .copied()
+        .filter(|item| state.player.inventory.quantity(*item) > 0)
+        .collect();
+    if sellable.is_empty() {
+        state
+            .term
+            .write_line("You have nothing this market wants to buy.")
+            .unwrap();
+        return;
+    }
+    let options: Vec<_> = sellable
+        .iter()
+        .map(|item| {
+            let item_type = state.world.items.get_item(*item).unwrap();
+            format!(
+                "{} - {} gold (you have {}{})",
+                item_label(item_type),
+                market::sell_price(city, &state.world.items, *item).unwrap(),
+                state.player.inventory.quantity(*item),
+                price_elsewhere(state, *item)
+            )
+        })
+        .collect();
+    let choice = Select::new()
+        .with_prompt("What would you like to sell?")
+        .items(&options)
+        .default(0)
+        .interact_on(&state.term)
+        .expect("Chose invalid option");
+    let item = sellable[choice];
+    let held = state.player.inventory.quantity(item);
+    let amount = prompt_quantity(
+        &state.term,
+        held,
+        &format!("You can only trade up to {}", held),
+    );
+    if amount == 0 {
+        return;
+    }
+
+    let city = state.world.map.get_node_mut(state.player.position).unwrap();
+    let message = match market::sell(
+        city,
+        &mut state.player,
+        &state.world.items,
+        item,
+        amount,
+        state.clock.day(),
+    ) {
+        Ok(total) => {
+            pass_time(state, TRADE_HOURS);
+            format!("You received {} gold.", total)
+        }
+        Err(err) => err.to_string(),
+    };
+    state.term.write_line(&message).unwrap();
+}
+
+fn describe_recipe(recipe: &Recipe, items: &ItemDatabase) -> String {
+    let list = |ingredients: &[(ItemIndex, u32)]| {
+        ingredients
+            .iter()
+            .map(|(item, qty)| format!("{} {}", qty, items.get_item(*item).unwrap().name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "{}: {} -> {} ({} days)",
+        recipe.name,
+        list(&recipe.inputs),
+        list(&recipe.outputs),
+        recipe.days
+    )
+}
+
+fn craft_menu(state: &mut GameState) {
+    let city = state.world.map.get_node(state.player.position).unwrap();
+    let available: Vec<_> = state
+        .world
+        .recipes
+        .iter()
+        .filter(|(_, recipe)| recipe.can_run_in(city).is_ok())
+        .collect();
+    if available.is_empty() {
+        state
+            .term
+            .write_line("There are no workshops you can use here.")
+            .unwrap();
+        thread::sleep(Duration::from_secs(1));
+        return;
+    }
+    let mut options: Vec<_> = available
+        .iter()
+        .map(|(_, recipe)| describe_recipe(recipe, &state.world.items))
+        .collect();
+    options.push("Leave".to_string());
+    let choice = Select::new()
+        .with_prompt("What would you like to make?")
+        .items(&options)
+        .default(0)
+        .interact_on(&state.term)
+        .expect("Chose invalid option");
+    let Some((_, recipe)) = available.get(choice) else {
+        return;
+    };
+    match recipe.run(&mut state.player.inventory, &state.world.items) {
+        Ok(_) => {
+            let days = recipe.days;
+            state
+                .term
+                .write_line(&format!("{} took {} days.", recipe.name, days))
+                .unwrap();
+            pass_time(state, days * HOURS_PER_DAY);
+        }
+        Err(err) => state.term.write_line(&err.to_string()).unwrap(),
+    }
+    thread::sleep(Duration::from_secs(1));
+}
+
+fn hire_guards_menu(state: &mut GameState) {
+    let affordable = state.player.gold / bandit::GUARD_HIRE_COST;
+    state
+        .term
+        .write_line(&format!(
+            "You have {} guards. Each new guard costs {} gold to hire and {} gold per day on the road.",
+            state.player.guards,
+            bandit::GUARD_HIRE_COST,
+            bandit::GUARD_WAGE
+        ))
+        .unwrap();
+    let hired = prompt_quantity(
+        &state.term,
+        affordable,
+        &format!("You can only afford {} more guards", affordable),
+    );
+    if hired == 0 {
+        return;
+    }
+    bandit::hire_guards(
+        &mut state.player,
+        hired,
+        &state.world.items,
+        state.clock.day(),
+    );
+}
+
+fn ledger_menu(state: &mut GameState) {
+    let transactions = state.player.ledger.transactions();
+    state.term.clear_screen().unwrap();
+    state
+        .term
+        .write_line(&format!(
+            "{}. Gold: {}. Net worth: {}.",
+            state.clock,
+            state.player.gold,
+            state.player.net_worth(&state.world.items)
+        ))
+        .unwrap();
+    for t in transactions.iter().rev().take(LEDGER_PAGE).rev() {
+        let goods = t
+            .goods
+            .and_then(|(item, qty)| {
+                let item = state.world.items.get_item(item)?;
+                Some(format!(" {} x{}", item.name, qty))
+            })
+            .unwrap_or_default();
+        state
+            .term
+            .write_line(&format!(
+                "Day {:>4} {:<5}{} {:+} gold (balance {})",
+                t.day, t.kind, goods, t.gold, t.balance
+            ))
+            .unwrap();
+    }
+    let export = Confirm::new()
+        .with_prompt("Export the full ledger to ledger.csv?")
+        .default(false)
+        .interact_on(&state.term)
+        .unwrap();
+    if export {
+        let message = match fs::write("ledger.csv", state.player.ledger.to_csv(&state.world.items))
+        {
+            Ok(_) => "Ledger written to ledger.csv".to_string(),
+            Err(err) => format!("Could not write ledger: {}", err),
+        };
+        state.term.write_line(&message).unwrap();
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn save_menu(state: &mut GameState) {
+    let save = SaveData::capture(&state.world, &state.player, state.clock, &state.rng);
+    let message = match save::write_save(&state.save_slot, &save) {
+        Ok(path) => format!("Game saved to {}", path.display()),
+        Err(err) => err.to_string(),
+    };
+    state.term.write_line(&message).unwrap();
+    thread::sleep(Duration::from_secs(1));
+}
+
+fn load_slot(state: &mut GameState) -> Result<(), SaveError> {
+    let restored = save::read_save(&state.save_slot)?.restore(&mut state.world)?;
+    state.player = restored.player;
+    state.clock = restored.clock;
+    state.rng = restored.rng;
+    Ok(())
+}
+
+fn load_menu(state: &mut GameState) {
+    let message = match load_slot(state) {
+        Ok(_) => "Game loaded.".to_string(),
+        Err(err) => err.to_string(),
+    };
+    state.term.write_line(&message).unwrap();
+    thread::sleep(Duration::from_secs(1));
+}
+
+/// Plain text summary of the world for `--headless` runs.
+fn print_report(state: &GameState) {
+    let start = state.world.map.get_node(state.player.position).unwrap();
+    println!(
+        "{}. Player at {} with {} gold.",
+        state.clock, start.name, state.player.gold
+    );
+    for (node_idx, city) in state.world.map.nodes() {
+        println!("\n{}", city.name);
+        for (edge_idx, to) in state
+            .world
+            .map
+            .get_connections(node_idx)
+            .into_iter()
+            .flatten()
+        {
+            let road = state.world.map.get_edge(*edge_idx).unwrap();
+            let to = state.world.map.get_node(*to).unwrap();
+            println!(
+                "  road: {} to {} ({} miles, {} toll, {})",
+                road.name,
+                to.name,
+                road.distance,
+                road.toll,
+                road_label(&state.world, *edge_idx)
             );
-            let conn_b = cities.get(&road.to).expect(
-                format!("Could not find city {} for road to {}", city.name, road.to).as_str(),
+        }
+        for item in city.market.listings() {
+            println!(
+                "  market: {} stock {} buy {} sell {}",
+                state.world.items.get_item(*item).unwrap().name,
+                city.inventory.quantity(*item),
+                market::buy_price(city, &state.world.items, *item).unwrap(),
+                market::sell_price(city, &state.world.items, *item).unwrap()
             );
-            world_map.insert_edge_undirected(road.clone(), *conn_a, *conn_b);
         }
     }
+}
 
-    let mut item_database = ItemDatabase::new();
-    for item in world.items {
-        item_database.insert(item);
+fn main() {
+    let args = Args::parse(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n\n{}", err, USAGE);
+        process::exit(2);
+    });
+    if args.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let (world_name, world_file) = match &args.world {
+        Some(path) => (
+            path.display().to_string(),
+            fs::read_to_string(path).unwrap_or_else(|err| {
+                eprintln!("Could not read {}: {}", path.display(), err);
+                process::exit(1);
+            }),
+        ),
+        None => (
+            "built in world".to_string(),
+            include_str!("../data/world.toml").to_string(),
+        ),
+    };
+    let world = match World::load(&world_file) {
+        Ok(world) => world,
+        Err(errors) => {
+            for err in errors {
+                eprintln!("{}: {}", world_name, err);
+            }
+            process::exit(1);
+        }
+    };
+    if args.check {
+        println!(
+            "{}: {} cities and {} items look good",
+            world_name,
+            world.map.nodes().count(),
+            world.items.len()
+        );
+        return;
     }
 
     let term = Term::stdout();
 
-    let player = PlayerState::new(*cities.get(&world.starting_position).unwrap());
+    let player = world.new_player();
 
-    let rng = Rng::with_seed(30);
+    let rng = args.seed.map_or_else(Rng::new, Rng::with_seed);
 
     let mut state = GameState {
-        world_map,
-        item_database,
+        world,
         term,
         player,
         rng,
+        clock: Clock::new(),
+        save_slot: args.slot,
+        starved: false,
     };
 
+    // A broken save should not lock the player out; saving again overwrites it.
+    // An explicit seed asks for a fresh run, so the slot is left alone.
+    if args.seed.is_some() {
+        if save::save_path(&state.save_slot).exists() {
+            eprintln!(
+                "Starting a new game with the given seed instead of resuming save slot {}.",
+                state.save_slot
+            );
+        }
+    } else if save::save_path(&state.save_slot).exists() {
+        if let Err(err) = load_slot(&mut state) {
+            eprintln!(
+                "Could not resume save slot {}: {}. Starting a new game.",
+                state.save_slot, err
+            );
+        }
+    }
+
+    if args.headless {
+        print_report(&state);
+        return;
+    }
+
     loop {
+        if state.starved {
+            state.term.clear_screen().unwrap();
+            state
+                .term
+                .write_line(&format!(
+                    "{}. Your caravan starved on the road. Game over.",
+                    state.clock
+                ))
+                .unwrap();
+            return;
+        }
         let player_position = state.player.position;
-        let current_city = state.world_map.get_node(player_position).unwrap();
+        let current_city = state.world.map.get_node(player_position).unwrap();
         let prompt = format!(
-            "You are located at {}.\n{}\nWhat road would you like to take now?",
-            current_city.name, current_city.description
+            "{}. You are located at {}.\n{}\nYou have {} gold and food for {} days. What would you like to do?",
+            state.clock,
+            current_city.name,
+            current_city.description,
+            state.player.gold,
+            food::days_of_food(&state.player, &state.world.items)
         );
         state.term.clear_screen().unwrap();
-        let edge;
-        let node;
-        {
-            let connections: Vec<_> = state
-                .world_map
-                .get_connections(state.player.position)
-                .unwrap()
-                .iter()
-                .collect();
-            let options: Vec<_> = connections
-                .iter()
-                .map(|(edge_idx, node_idx)| {
-                    let edge = state.world_map.get_edge(*edge_idx).unwrap();
-                    let node = state.world_map.get_node(*node_idx).unwrap();
-                    format!("{} to {} ({} miles)", edge.name, node.name, edge.distance)
-                })
-                .collect();
-            let choice = Select::new()
-                .with_prompt(prompt)
-                .items(&options)
-                .default(0)
-                .interact_on(&state.term)
-                .expect("Chose invalid option");
-            let (e, n) = connections.get(choice).unwrap();
-            edge = *e;
-            node = *n;
-        }
-
-        travel_to(&mut state, edge, node);
+        let term = state.term.clone();
+        let menu: Vec<MenuItem<&mut GameState>> = vec![
+            ("Travel", Box::new(travel_menu)),
+            ("Plan route", Box::new(plan_route_menu)),
+            ("Visit the market", Box::new(market_menu)),
+            ("Craft", Box::new(craft_menu)),
+            ("Hire guards", Box::new(hire_guards_menu)),
+            ("Review ledger", Box::new(ledger_menu)),
+            ("Save", Box::new(save_menu)),
+            ("Load", Box::new(load_menu)),
+        ];
+        prompt_menu(&term, &prompt, menu, &mut state);
     }
 }
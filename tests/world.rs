@@ -0,0 +1,824 @@
+use rust_trader::game::bandit::{self, Ambush, AmbushResponse};
+use rust_trader::game::clock::{Clock, Season};
+use rust_trader::game::crafting::{self, CraftError};
+use rust_trader::game::event::{Consequences, EventContext};
+use rust_trader::game::food::{self, Hunger};
+use rust_trader::game::item::{Batch, Inventory, InventoryError, ItemTag};
+use rust_trader::game::ledger::TransactionKind;
+use rust_trader::game::market::{self, TradeError};
+use rust_trader::game::player::PlayerState;
+use rust_trader::game::route::{self, RouteCost, RouteKind};
+use rust_trader::game::save::{BatchSave, SaveData, SaveError, SAVE_VERSION};
+use rust_trader::game::world::World;
+use rust_trader::graph::{Connectivity, Path, Search};
+
+const WORLD: &str = include_str!("../data/world.toml");
+
+#[test]
+fn loads_bundled_world() {
+    let world = World::load(WORLD).expect("bundled world should load");
+    let start = world.map.get_node(world.starting_position).unwrap();
+    assert_eq!(start.name, "Jericho");
+    assert_eq!(world.map.nodes().count(), 7);
+
+    let jericho = world.city_index("Jericho").unwrap();
+    let coralith = world.city_index("Coralith").unwrap();
+    let path = world
+        .map
+        .bfs(jericho, coralith)
+        .expect("Coralith is reachable");
+    assert_eq!(path.nodes.first(), Some(&jericho));
+    assert_eq!(path.nodes.last(), Some(&coralith));
+}
+
+#[test]
+fn planned_routes_follow_real_roads() {
+    let world = World::load(WORLD).unwrap();
+    let maritopia = world.city_index("Maritopia").unwrap();
+    let aquavista = world.city_index("Aquavista").unwrap();
+    let path = world.map.dijkstra(maritopia, aquavista).unwrap();
+    assert_eq!(path.nodes, [maritopia, aquavista]);
+    let road = world.map.get_edge(path.edges[0]).unwrap();
+    assert_eq!(road.name, "Saltspire Causeway");
+    assert_eq!(path.cost, road.distance as u64);
+
+    let jericho = world.city_index("Jericho").unwrap();
+    let coralith = world.city_index("Coralith").unwrap();
+    let path = world.map.dijkstra(jericho, coralith).unwrap();
+    assert_eq!(path.edges.len() + 1, path.nodes.len());
+    let miles: u64 = path
+        .legs()
+        .map(|(edge, _)| world.map.get_edge(edge).unwrap().distance as u64)
+        .sum();
+    assert_eq!(path.cost, miles);
+}
+
+#[test]
+fn travel_table_and_price_comparison() {
+    let world = World::load(WORLD).unwrap();
+    let maritopia = world.city_index("Maritopia").unwrap();
+    let aquavista = world.city_index("Aquavista").unwrap();
+    assert_eq!(world.distances.distance(maritopia, aquavista), Some(40));
+    assert_eq!(world.distances.distance(maritopia, maritopia), Some(0));
+
+    let tree = world.map.shortest_path_tree(maritopia);
+    for (city, _) in world.map.nodes() {
+        assert_eq!(
+            tree.distance(city),
+            world.distances.distance(maritopia, city)
+        );
+    }
+
+    let fish = *world.items.get_index("fish").unwrap();
+    let (best, price, miles) = world.best_market_for(fish, maritopia).unwrap();
+    assert_ne!(best, maritopia);
+    assert_eq!(world.distances.distance(maritopia, best), Some(miles));
+    for (_, city) in world.map.nodes().filter(|(n, _)| *n != maritopia) {
+        if let Some(other) = market::sell_price(city, &world.items, fish) {
+            assert!(other <= price, "{} pays more", city.name);
+        }
+    }
+}
+
+#[test]
+fn item_tags_are_queryable() {
+    let world = World::load(WORLD).unwrap();
+    let edible: Vec<&str> = world
+        .items
+        .items_with_tag(ItemTag::Edible)
+        .map(|(_, item)| item.key.as_str())
+        .collect();
+    assert!(edible.contains(&"bread"));
+    assert!(edible.contains(&"fish"));
+    assert!(!edible.contains(&"salt"));
+    assert!(world
+        .items
+        .get_item_by_key("bread")
+        .unwrap()
+        .has_tag(ItemTag::Craftible));
+}
+
+#[test]
+fn cities_and_players_run_recipes() {
+    let mut world = World::load(WORLD).unwrap();
+    let grain = *world.items.get_index("grain").unwrap();
+    let bread = *world.items.get_index("bread").unwrap();
+    let bake = *world.recipes.get_index("bake_bread").unwrap();
+
+    let tritonar = world.city_index("Tritonar").unwrap();
+    let city = world.map.get_node_mut(tritonar).unwrap();
+    let (grain_before, bread_before) = (
+        city.inventory.quantity(grain),
+        city.inventory.quantity(bread),
+    );
+    crafting::run_production(city, &world.recipes, &world.items, 5.0);
+    assert_eq!(city.inventory.quantity(grain), grain_before - 15);
+    assert_eq!(city.inventory.quantity(bread), bread_before + 5);
+
+    let recipe = world.recipes.get_recipe(bake).unwrap();
+    let jericho = world.city("Jericho").unwrap();
+    assert_eq!(
+        recipe.can_run_in(jericho),
+        Err(CraftError::MissingWorkshop("bakery".into()))
+    );
+    let mut player = PlayerState::new(tritonar, 0);
+    assert_eq!(
+        recipe.run(&mut player.inventory, &world.items),
+        Err(CraftError::MissingInput(grain))
+    );
+    player.inventory.add_item(grain, 7);
+    recipe.run(&mut player.inventory, &world.items).unwrap();
+    recipe.run(&mut player.inventory, &world.items).unwrap();
+    assert_eq!(player.inventory.quantity(grain), 1);
+    assert_eq!(player.inventory.quantity(bread), 2);
+
+    // One wood weighs 4 but fires into two pots weighing 3 each.
+    let wood = *world.items.get_index("wood").unwrap();
+    let pottery = *world.items.get_index("pottery").unwrap();
+    let fire = *world.recipes.get_index("fire_pottery").unwrap();
+    let recipe = world.recipes.get_recipe(fire).unwrap();
+    let mut cart = Inventory::with_capacity(5);
+    cart.add_item(wood, 1);
+    assert_eq!(
+        recipe.run(&mut cart, &world.items),
+        Err(CraftError::TooHeavy)
+    );
+    assert_eq!(cart.quantity(wood), 1);
+    assert_eq!(cart.quantity(pottery), 0);
+    cart.capacity = Some(6);
+    recipe.run(&mut cart, &world.items).unwrap();
+    assert_eq!(cart.quantity(pottery), 2);
+}
+
+#[test]
+fn trades_against_real_markets() {
+    let mut world = World::load(WORLD).unwrap();
+    let mut player = PlayerState::new(world.starting_position, world.starting_gold);
+    let salt = *world.items.get_index("salt").unwrap();
+
+    let city = world.map.get_node_mut(world.starting_position).unwrap();
+    let stock = city.inventory.quantity(salt);
+    let paid = market::buy(city, &mut player, &world.items, salt, 5, 0).unwrap();
+    assert_eq!(player.gold, world.starting_gold - paid);
+    assert_eq!(player.inventory.quantity(salt), 5);
+    assert_eq!(city.inventory.quantity(salt), stock - 5);
+
+    let received = market::sell(city, &mut player, &world.items, salt, 5, 1).unwrap();
+    assert!(received < paid, "the spread should cost the player");
+    assert_eq!(player.ledger.transactions().len(), 2);
+}
+
+#[test]
+fn failed_trades_change_nothing() {
+    let mut world = World::load(WORLD).unwrap();
+    let mut player = PlayerState::new(world.starting_position, 0);
+    let iron = *world.items.get_index("iron").unwrap();
+    let fish = *world.items.get_index("fish").unwrap();
+    let salt = *world.items.get_index("salt").unwrap();
+
+    let city = world.map.get_node_mut(world.starting_position).unwrap();
+    let stock = city.inventory.quantity(salt);
+    assert_eq!(
+        market::buy(city, &mut player, &world.items, iron, 1, 0),
+        Err(TradeError::NotTraded)
+    );
+    assert_eq!(
+        market::buy(city, &mut player, &world.items, fish, 1, 0),
+        Err(TradeError::OutOfStock)
+    );
+    assert_eq!(
+        market::buy(city, &mut player, &world.items, salt, 1, 0),
+        Err(TradeError::NotEnoughGold)
+    );
+    assert_eq!(
+        market::sell(city, &mut player, &world.items, salt, 1, 0),
+        Err(TradeError::NotEnoughItems)
+    );
+    assert_eq!(city.inventory.quantity(salt), stock);
+    assert!(player.inventory.is_empty());
+    assert!(player.ledger.transactions().is_empty());
+}
+
+#[test]
+fn ledger_records_every_change_in_gold() {
+    let world = World::load(WORLD).unwrap();
+    let salt = *world.items.get_index("salt").unwrap();
+    let mut player = PlayerState::new(world.starting_position, 20);
+
+    player.inventory.add_item(salt, 4);
+    let gift = player.transact(2, TransactionKind::Gift, 0, Some((salt, 4)), &world.items);
+    assert_eq!(gift, Some(20));
+    let toll = player.transact(3, TransactionKind::Toll, -5, None, &world.items);
+    assert_eq!(toll, Some(15));
+    let refused = player.transact(4, TransactionKind::Toll, -100, None, &world.items);
+    assert_eq!(refused, None);
+    assert_eq!(player.gold, 15);
+    assert_eq!(player.ledger.transactions().len(), 2);
+
+    let history: Vec<_> = player.ledger.net_worth_history().collect();
+    assert_eq!(history, [(2, 40), (3, 35)]);
+    assert_eq!(
+        player.ledger.to_csv(&world.items),
+        "day,kind,item,quantity,gold,balance,net_worth\n\
+         2,gift,salt,4,0,20,40\n\
+         3,toll,,0,-5,15,35"
+    );
+}
+
+#[test]
+fn save_round_trips_through_toml() {
+    let mut world = World::load(WORLD).unwrap();
+    let mut player = PlayerState::new(world.starting_position, world.starting_gold);
+    let spice = *world.items.get_index("spice").unwrap();
+    let city = world.map.get_node_mut(world.starting_position).unwrap();
+    market::buy(city, &mut player, &world.items, spice, 1, 0).unwrap();
+    let maritopia = world.city_index("Maritopia").unwrap();
+    player.goto(maritopia);
+
+    let rng = fastrand::Rng::with_seed(u64::MAX - 7);
+    let saved =
+        toml::to_string(&SaveData::capture(&world, &player, Clock::at(12, 5), &rng)).unwrap();
+
+    let mut fresh = World::load(WORLD).unwrap();
+    let restored = toml::from_str::<SaveData>(&saved)
+        .unwrap()
+        .restore(&mut fresh)
+        .unwrap();
+    assert_eq!(restored.clock, Clock::at(12, 5));
+    assert_eq!(restored.rng.get_seed(), rng.get_seed());
+    assert_eq!(restored.player.position, maritopia);
+    assert_eq!(restored.player.gold, player.gold);
+    assert_eq!(restored.player.inventory.quantity(spice), 1);
+    assert_eq!(restored.player.ledger.transactions().len(), 1);
+    assert_eq!(
+        fresh.city("Jericho").unwrap().inventory.quantity(spice),
+        world.city("Jericho").unwrap().inventory.quantity(spice)
+    );
+}
+
+#[test]
+fn saves_keep_city_production_and_restock_progress() {
+    let mut world = World::load(WORLD).unwrap();
+    let player = world.new_player();
+    let tritonar = world.city_index("Tritonar").unwrap();
+    let city = world.map.get_node_mut(tritonar).unwrap();
+    crafting::run_production(city, &world.recipes, &world.items, 0.5);
+    // Knock stock off equilibrium so restocking leaves a fraction behind.
+    for item in city.market.listings().to_vec() {
+        let stock = city.inventory.quantity(item);
+        city.inventory.remove_item(item, stock / 2).unwrap();
+    }
+    market::restock(city, 0.3);
+
+    let rng = fastrand::Rng::with_seed(3);
+    let saved =
+        toml::to_string(&SaveData::capture(&world, &player, Clock::at(1, 0), &rng)).unwrap();
+    let mut fresh = World::load(WORLD).unwrap();
+    toml::from_str::<SaveData>(&saved)
+        .unwrap()
+        .restore(&mut fresh)
+        .unwrap();
+
+    let (before, after) = (
+        world.city("Tritonar").unwrap(),
+        fresh.city("Tritonar").unwrap(),
+    );
+    assert!(!after.production.is_empty());
+    for (old, new) in before.production.iter().zip(&after.production) {
+        assert_eq!(new.elapsed, old.elapsed);
+    }
+    assert!(before.market.listings().iter().any(|item| before
+        .market
+        .economy(*item)
+        .unwrap()
+        .carry
+        != 0.0));
+    for item in before.market.listings() {
+        assert_eq!(
+            after.market.economy(*item).unwrap().carry,
+            before.market.economy(*item).unwrap().carry
+        );
+    }
+}
+
+#[test]
+fn rejected_saves_leave_the_world_untouched() {
+    let mut world = World::load(WORLD).unwrap();
+    let salt = *world.items.get_index("salt").unwrap();
+    let player = world.new_player();
+    let rng = fastrand::Rng::with_seed(7);
+    let stock = world.city("Jericho").unwrap().inventory.quantity(salt);
+
+    let mut save = SaveData::capture(&world, &player, Clock::at(0, 0), &rng);
+    save.cities.last_mut().unwrap().name = "Atlantis".into();
+    save.cities[0].inventory.insert("salt".into(), 0);
+    assert!(matches!(
+        save.restore(&mut world),
+        Err(SaveError::UnknownCity(name)) if name == "Atlantis"
+    ));
+    assert_eq!(
+        world.city("Jericho").unwrap().inventory.quantity(salt),
+        stock
+    );
+
+    let mut save = SaveData::capture(&world, &player, Clock::at(0, 0), &rng);
+    save.player.inventory.push(BatchSave {
+        item: "unobtainium".into(),
+        quantity: 1,
+        acquired: 0,
+    });
+    assert!(matches!(
+        save.restore(&mut world),
+        Err(SaveError::UnknownItem(key)) if key == "unobtainium"
+    ));
+
+    let mut save = SaveData::capture(&world, &player, Clock::at(0, 0), &rng);
+    save.version = SAVE_VERSION + 1;
+    assert!(matches!(
+        save.restore(&mut world),
+        Err(SaveError::Version(version)) if version == SAVE_VERSION + 1
+    ));
+}
+
+#[test]
+fn inventory_enforces_stock_and_capacity() {
+    let mut world = World::load(WORLD).unwrap();
+    let iron = *world.items.get_index("iron").unwrap();
+    let mut cart = Inventory::with_capacity(22);
+    let mut warehouse = Inventory::new();
+    warehouse.add_item(iron, 10);
+
+    warehouse
+        .transfer_to(&mut cart, iron, 4, &world.items)
+        .unwrap();
+    assert_eq!(cart.weight(&world.items), 20);
+    assert_eq!(cart.remaining_capacity(&world.items), Some(2));
+    assert_eq!(
+        warehouse.transfer_to(&mut cart, iron, 1, &world.items),
+        Err(InventoryError::OverCapacity {
+            capacity: 22,
+            weight: 25
+        })
+    );
+    assert_eq!(warehouse.quantity(iron), 6);
+    // Huge amounts saturate rather than wrapping round under the limit.
+    assert_eq!(
+        cart.try_add_item(iron, u32::MAX / 4, &world.items),
+        Err(InventoryError::OverCapacity {
+            capacity: 22,
+            weight: u32::MAX
+        })
+    );
+    assert_eq!(cart.quantity(iron), 4);
+    assert_eq!(
+        cart.remove_item(iron, 5),
+        Err(InventoryError::Insufficient {
+            item: iron,
+            have: 4,
+            wanted: 5
+        })
+    );
+    assert_eq!(cart.remove_item(iron, 4), Ok(0));
+    assert!(cart.is_empty());
+
+    let mut player = world.new_player();
+    player.gold = 10_000;
+    player.inventory.add_item(iron, 30);
+    let thalassar = world.city_index("Thalassar").unwrap();
+    let city = world.map.get_node_mut(thalassar).unwrap();
+    assert_eq!(
+        market::buy(city, &mut player, &world.items, iron, 40, 0),
+        Err(TradeError::TooHeavy)
+    );
+    assert_eq!(player.gold, 10_000);
+}
+
+#[test]
+fn events_respect_conditions_and_apply_outcomes() {
+    let world = World::load(WORLD).unwrap();
+    let maritopia = world.city_index("Maritopia").unwrap();
+    let aquavista = world.city_index("Aquavista").unwrap();
+    let (road_idx, _) = world
+        .map
+        .get_connections(maritopia)
+        .unwrap()
+        .iter()
+        .find(|(_, to)| *to == aquavista)
+        .unwrap();
+    let mut player = PlayerState::new(maritopia, 3);
+    let ctx = EventContext {
+        road: world.map.get_edge(*road_idx).unwrap(),
+        from: world.city("Maritopia").unwrap(),
+        to: world.city("Aquavista").unwrap(),
+        player: &player,
+        season: Season::Spring,
+    };
+
+    let mut rng = fastrand::Rng::with_seed(7);
+    let mut seen = std::collections::HashSet::new();
+    for _ in 0..500 {
+        seen.insert(world.events.pick(&mut rng, &ctx).unwrap().key.as_str());
+    }
+    let mut seen: Vec<_> = seen.into_iter().collect();
+    seen.sort();
+    // No spice for the collector and no desert for the mirage.
+    assert_eq!(
+        seen,
+        [
+            "beggar",
+            "broken_wheel",
+            "roadside_fishermen",
+            "washed_out_road"
+        ]
+    );
+    let fishermen = world.events.get("roadside_fishermen").unwrap();
+    assert_eq!(fishermen.available_choices(&ctx).len(), 1);
+
+    let beggar = world.events.get("beggar").unwrap();
+    let give = beggar.available_choices(&ctx)[0];
+    assert_eq!(
+        give.apply(&mut player, &world.items, 4),
+        Consequences::default()
+    );
+    let berries = *world.items.get_index("berries").unwrap();
+    assert_eq!(player.gold, 2);
+    assert_eq!(player.inventory.quantity(berries), 3);
+    let last = |player: &PlayerState| {
+        let t = player.ledger.transactions().last().unwrap().clone();
+        (t.kind, t.goods, t.gold)
+    };
+    assert_eq!(
+        last(&player),
+        (TransactionKind::Buy, Some((berries, 3)), -1)
+    );
+
+    // Goods traded or lost on the road show up in the ledger too.
+    let spice = *world.items.get_index("spice").unwrap();
+    player.inventory.add_item(spice, 2);
+    let collector = world.events.get("spice_collector").unwrap();
+    collector.choices[0].apply(&mut player, &world.items, 5);
+    assert_eq!(
+        last(&player),
+        (TransactionKind::Sell, Some((spice, 2)), 130)
+    );
+    assert_eq!(player.gold, 132);
+
+    let tools = *world.items.get_index("tools").unwrap();
+    player.inventory.add_item(tools, 1);
+    let wheel = world.events.get("broken_wheel").unwrap();
+    wheel.choices[0].apply(&mut player, &world.items, 6);
+    assert_eq!(last(&player), (TransactionKind::Loss, Some((tools, 1)), 0));
+    assert_eq!(player.ledger.transactions().len(), 3);
+
+    let washout = world.events.get("washed_out_road").unwrap();
+    let detour = &washout.choices[1];
+    assert_eq!(detour.apply(&mut player, &world.items, 4).detour_miles, 15);
+}
+
+#[test]
+fn bandits_take_cargo_and_guards_cost_wages() {
+    let world = World::load(WORLD).unwrap();
+    let wine = *world.items.get_index("wine").unwrap();
+    let mut player = world.new_player();
+    player.inventory.add_item(wine, 10);
+    let mut rng = fastrand::Rng::with_seed(3);
+
+    let ambush = Ambush { bandits: 2 };
+    let paid = ambush.resolve(AmbushResponse::Pay, &mut player, &world.items, &mut rng, 1);
+    assert!(paid.escaped);
+    assert_eq!(player.gold, 100 - ambush.tribute());
+
+    let robbed = ambush.resolve(
+        AmbushResponse::Surrender,
+        &mut player,
+        &world.items,
+        &mut rng,
+        2,
+    );
+    assert_eq!(robbed.stolen, [(wine, 3)]);
+    assert_eq!(player.inventory.quantity(wine), 7);
+
+    // Plenty of guards scare bandits off entirely.
+    for _ in 0..1000 {
+        assert_eq!(bandit::roll_ambush(&mut rng, 1.0, 10), None);
+    }
+
+    player.guards = 0;
+    player.gold = 85;
+    assert!(!bandit::hire_guards(&mut player, 4, &world.items, 3));
+    assert!(bandit::hire_guards(&mut player, 3, &world.items, 3));
+    assert_eq!(player.gold, 10);
+    assert_eq!(bandit::pay_guards(&mut player, 2, &world.items, 3), 1);
+    assert_eq!(player.guards, 2);
+    assert_eq!(player.gold, 2);
+    let kinds: Vec<_> = player.ledger.transactions()[2..]
+        .iter()
+        .map(|t| (t.kind, t.gold))
+        .collect();
+    assert_eq!(
+        kinds,
+        [(TransactionKind::Hire, -75), (TransactionKind::Wages, -8)]
+    );
+}
+
+#[test]
+fn seasons_shift_prices_and_events() {
+    let mut world = World::load(WORLD).unwrap();
+    let maritopia = world.city_index("Maritopia").unwrap();
+    let wood = *world.items.get_index("wood").unwrap();
+    let price = |world: &World| {
+        market::buy_price(world.city("Maritopia").unwrap(), &world.items, wood).unwrap()
+    };
+    let spring = price(&world);
+    world
+        .map
+        .get_node_mut(maritopia)
+        .unwrap()
+        .market
+        .set_season(Season::Winter);
+    assert!(price(&world) > spring);
+
+    let mirage = world.events.get("desert_mirage").unwrap();
+    let jericho = world.city_index("Jericho").unwrap();
+    let (road_idx, to) = world
+        .map
+        .get_connections(jericho)
+        .unwrap()
+        .iter()
+        .next()
+        .unwrap();
+    let player = world.new_player();
+    let mut ctx = EventContext {
+        road: world.map.get_edge(*road_idx).unwrap(),
+        from: world.city("Jericho").unwrap(),
+        to: world.map.get_node(*to).unwrap(),
+        player: &player,
+        season: Season::Spring,
+    };
+    assert!(!mirage.conditions.holds(&ctx));
+    ctx.season = Season::Summer;
+    assert!(mirage.conditions.holds(&ctx));
+}
+
+#[test]
+fn caravans_eat_cheapest_food_and_starve_without_it() {
+    let world = World::load(WORLD).unwrap();
+    let bread = *world.items.get_index("bread").unwrap();
+    let berries = *world.items.get_index("berries").unwrap();
+    let mut player = world.new_player();
+    player.guards = 1;
+    player.inventory.add_item(bread, 3);
+    player.inventory.add_item(berries, 4);
+    assert_eq!(food::daily_need(&player), 8);
+    assert_eq!(food::days_of_food(&player, &world.items), 2);
+
+    // Bread gives four times the nutrition of berries for less than their price.
+    assert_eq!(food::eat(&mut player, &world.items), [(bread, 2)]);
+    assert_eq!(player.hunger(), Hunger::Fed);
+    assert_eq!(
+        food::eat(&mut player, &world.items),
+        [(bread, 1), (berries, 4)]
+    );
+    assert_eq!(player.hunger(), Hunger::Fed);
+
+    assert!(food::eat(&mut player, &world.items).is_empty());
+    assert_eq!(player.hunger(), Hunger::Hungry { days: 1 });
+    assert!(player.hunger().speed_factor() < 1.0);
+    for _ in 1..food::STARVATION_DAYS {
+        food::eat(&mut player, &world.items);
+    }
+    assert_eq!(player.hunger(), Hunger::Starved);
+}
+
+#[test]
+fn perishable_batches_spoil_and_survive_saves() {
+    let mut world = World::load(WORLD).unwrap();
+    let bread = *world.items.get_index("bread").unwrap();
+    let stale = *world.items.get_index("stale_bread").unwrap();
+    let fish = *world.items.get_index("fish").unwrap();
+    let salt = *world.items.get_index("salt").unwrap();
+    let mut player = world.new_player();
+    player.inventory.add_item(bread, 4);
+    player.inventory.add_item(salt, 2);
+    player.inventory.advance(2, &world.items);
+    player.inventory.add_item(bread, 3);
+    player.inventory.add_item(fish, 5);
+    assert_eq!(player.inventory.batches(bread).len(), 2);
+
+    // Selling takes the oldest loaves first.
+    let mut market = Inventory::new();
+    player
+        .inventory
+        .transfer_to(&mut market, bread, 5, &world.items)
+        .unwrap();
+    assert_eq!(
+        market.batches(bread),
+        [
+            Batch {
+                quantity: 4,
+                acquired: 0
+            },
+            Batch {
+                quantity: 1,
+                acquired: 2
+            }
+        ]
+    );
+
+    let rng = fastrand::Rng::with_seed(1);
+    let saved =
+        toml::to_string(&SaveData::capture(&world, &player, Clock::at(3, 0), &rng)).unwrap();
+    let mut restored = toml::from_str::<SaveData>(&saved)
+        .unwrap()
+        .restore(&mut world)
+        .unwrap()
+        .player;
+    assert_eq!(
+        restored.inventory.batches(fish),
+        player.inventory.batches(fish)
+    );
+
+    assert_eq!(
+        restored.inventory.advance(7, &world.items),
+        [(bread, 2), (fish, 5)]
+    );
+    assert_eq!(restored.inventory.quantity(stale), 2);
+    assert_eq!(restored.inventory.quantity(salt), 2);
+    assert_eq!(restored.inventory.advance(20, &world.items), [(stale, 2)]);
+    assert!(restored.inventory.advance(21, &world.items).is_empty());
+}
+
+#[test]
+fn market_goods_are_fresh_when_bought() {
+    let mut world = World::load(WORLD).unwrap();
+    let fish = *world.items.get_index("fish").unwrap();
+    let mut player = world.new_player();
+    // Long past the shelf life of the fish the market started with.
+    player.inventory.advance(30, &world.items);
+
+    let maritopia = world.city_index("Maritopia").unwrap();
+    let city = world.map.get_node_mut(maritopia).unwrap();
+    market::buy(city, &mut player, &world.items, fish, 2, 30).unwrap();
+    assert_eq!(
+        player.inventory.batches(fish),
+        [Batch {
+            quantity: 2,
+            acquired: 30
+        }]
+    );
+    assert!(player.inventory.advance(31, &world.items).is_empty());
+    assert_eq!(player.inventory.advance(34, &world.items), [(fish, 2)]);
+}
+
+#[test]
+fn version_one_saves_load_as_undated_batches() {
+    let saved = r#"
+        version = 1
+        day = 3
+        rng_state = "0000000000000001"
+
+        [player]
+        position = "Jericho"
+        gold = 50
+        visited_places = ["Jericho"]
+        ledger = []
+
+        [player.inventory]
+        fish = 2
+        salt = 5
+
+        [[cities]]
+        name = "Jericho"
+
+        [cities.inventory]
+        salt = 7
+    "#;
+    let mut world = World::load(WORLD).unwrap();
+    let fish = *world.items.get_index("fish").unwrap();
+    let salt = *world.items.get_index("salt").unwrap();
+    let mut player = toml::from_str::<SaveData>(saved)
+        .unwrap()
+        .restore(&mut world)
+        .unwrap()
+        .player;
+    assert_eq!(
+        player.inventory.batches(fish),
+        [Batch {
+            quantity: 2,
+            acquired: 0
+        }]
+    );
+    assert_eq!(player.inventory.quantity(salt), 5);
+    assert_eq!(world.city("Jericho").unwrap().inventory.quantity(salt), 7);
+    assert_eq!(player.inventory.advance(4, &world.items), [(fish, 2)]);
+}
+
+#[test]
+fn route_planner_minimizes_each_measure() {
+    let world = World::load(WORLD).unwrap();
+    let mut player = PlayerState::new(world.starting_position, 100);
+    let road_score = |kind: RouteKind, path: &Path<RouteCost>, player: &PlayerState| -> f32 {
+        path.edges
+            .iter()
+            .map(|edge| {
+                let road = world.map.get_edge(*edge).unwrap();
+                kind.road_cost(road, player, &world.items).score
+            })
+            .sum()
+    };
+    for (from, _) in world.map.nodes() {
+        player.position = from;
+        for (to, _) in world.map.nodes() {
+            let routes: Vec<_> = RouteKind::ALL
+                .iter()
+                .map(|kind| route::plan_route(&world, &player, to, *kind).unwrap())
+                .collect();
+            for (kind, path) in RouteKind::ALL.into_iter().zip(&routes) {
+                assert_eq!(path.nodes.first(), Some(&from));
+                assert_eq!(path.nodes.last(), Some(&to));
+                for (leg, (edge, node)) in path.legs().enumerate() {
+                    let roads = world.map.get_connections(path.nodes[leg]).unwrap();
+                    assert!(roads.contains(&(edge, node)));
+                }
+                // Each route scores best by its own measure, whatever the others found.
+                for other in &routes {
+                    assert!(path.cost.score <= road_score(kind, other, &player) + 1e-3);
+                }
+            }
+        }
+    }
+
+    let name = |node| world.map.get_node(node).unwrap().name.as_str();
+    let via = |path: Path<RouteCost>| -> Vec<&str> { path.nodes.into_iter().map(name).collect() };
+    let maritopia = world.city_index("Maritopia").unwrap();
+    let aquavista = world.city_index("Aquavista").unwrap();
+    let tritonar = world.city_index("Tritonar").unwrap();
+    player.position = maritopia;
+    let plan = |to, kind| route::plan_route(&world, &player, to, kind).unwrap();
+    assert_eq!(
+        via(plan(tritonar, RouteKind::Fastest)),
+        ["Maritopia", "Tritonar"]
+    );
+    assert_eq!(
+        via(plan(tritonar, RouteKind::Safest)),
+        ["Maritopia", "Seraphis", "Tritonar"]
+    );
+    // The causeway to Aquavista is short but tolled.
+    assert_eq!(
+        via(plan(aquavista, RouteKind::Fastest)),
+        ["Maritopia", "Aquavista"]
+    );
+    assert_eq!(
+        via(plan(aquavista, RouteKind::Cheapest)),
+        ["Maritopia", "Tritonar", "Seraphis", "Aquavista"]
+    );
+}
+
+#[test]
+fn heavy_carts_and_hunger_slow_the_caravan() {
+    let world = World::load(WORLD).unwrap();
+    let maritopia = world.city_index("Maritopia").unwrap();
+    let aquavista = world.city_index("Aquavista").unwrap();
+    let mut player = PlayerState::new(maritopia, 100);
+    player.inventory.capacity = world.carry_capacity;
+    let hours = |player: &PlayerState| {
+        route::plan_route(&world, player, aquavista, RouteKind::Fastest)
+            .unwrap()
+            .cost
+            .hours
+    };
+    // Saltspire Causeway is 40 miles.
+    let empty = hours(&player);
+    assert_eq!(empty, 40.0 * 24.0 / route::MILES_PER_DAY as f32);
+
+    let salt = *world.items.get_index("salt").unwrap();
+    let capacity = player.inventory.capacity.unwrap();
+    let weight = world.items.get_item(salt).unwrap().weight;
+    player.inventory.add_item(salt, capacity / weight);
+    let loaded = hours(&player);
+    assert!(loaded > empty);
+
+    player.hunger_days = 1;
+    assert!(hours(&player) > loaded);
+}
+
+#[test]
+fn chokepoints_are_the_only_way_through() {
+    let world = World::load(WORLD).unwrap();
+    assert_eq!(world.map.connected_components().len(), 1);
+
+    // Jericho hangs off Maritopia by a single trail.
+    let chokepoints: Vec<&str> = world
+        .chokepoints
+        .iter()
+        .map(|road| world.map.get_edge(*road).unwrap().name.as_str())
+        .collect();
+    assert_eq!(chokepoints, ["Foamcrest Trail"]);
+    let hubs: Vec<&str> = world
+        .map
+        .articulation_points()
+        .into_iter()
+        .map(|city| world.map.get_node(city).unwrap().name.as_str())
+        .collect();
+    assert_eq!(hubs, ["Maritopia"]);
+}